@@ -0,0 +1,397 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::func::{Activation, ActivationKind};
+use crate::lin_alg::Matrix;
+
+#[derive(Clone, Copy, Debug)]
+enum Op {
+    Leaf,
+    MatMul(usize, usize),
+    // both sides are broadcast to the shape of the result
+    Add(usize, usize),
+    Sub(usize, usize),
+    Had(usize, usize),
+    Transpose(usize),
+    Strech(usize, f64),
+    Activate(usize, ActivationKind),
+    Sum(usize),
+    Mean(usize),
+    // the sum of every row, one column
+    RowSum(usize),
+    // the sum of every column, one row
+    ColSum(usize)
+}
+
+struct Node {
+    value: Matrix,
+    op: Op,
+    grad: Option<Matrix>
+}
+
+// nodes are only ever pushed, so their order on the tape is already topological
+pub struct Tape {
+    nodes: RefCell<Vec<Node>>
+}
+
+#[derive(Clone)]
+pub struct Var {
+    tape: Rc<Tape>,
+    index: usize
+}
+
+// the shape two operands broadcast to, a side of 1 stretches over the other side
+fn broadcast_shape(a: (usize, usize), b: (usize, usize)) -> (usize, usize) {
+    let side = |a: usize, b: usize| {
+        if a == b || b == 1 { a } else if a == 1 { b } else { panic!("The shapes can't be broadcast!") }
+    };
+    (side(a.0, b.0), side(a.1, b.1))
+}
+
+fn broadcast(matrix: &Matrix, shape: (usize, usize)) -> Matrix {
+    if matrix.shape() == shape { return matrix.clone() }
+    let (x, y) = matrix.shape();
+    let mut out = Matrix::zeros(shape);
+    for j in 0..shape.1 {
+        for i in 0..shape.0 {
+            out.change_place((i, j), matrix.get(i % x, j % y))
+        }
+    };
+    out
+}
+
+// the adjoint of broadcast, sums the gradient back over the stretched sides
+fn unbroadcast(grad: &Matrix, shape: (usize, usize)) -> Matrix {
+    if grad.shape() == shape { return grad.clone() }
+    let mut out = Matrix::zeros(shape);
+    for j in 0..grad.shape().1 {
+        for i in 0..grad.shape().0 {
+            let (x, y) = (i % shape.0, j % shape.1);
+            out.change_place((x, y), out.get(x, y) + grad.get(i, j))
+        }
+    };
+    out
+}
+
+fn scalar(val: f64) -> Matrix {
+    Matrix::from_vec(vec![vec![val]])
+}
+
+fn accumulate(grads: &mut [Option<Matrix>], index: usize, grad: Matrix) {
+    grads[index] = Some(match grads[index].take() {
+        Some(sum) => sum + grad,
+        None => grad
+    })
+}
+
+impl Tape {
+    pub fn new() -> Rc<Self> {
+        Rc::new(Self { nodes: RefCell::new(Vec::new()) })
+    }
+
+    pub fn var(self: &Rc<Self>, value: Matrix) -> Var {
+        self.push(value, Op::Leaf)
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn push(self: &Rc<Self>, value: Matrix, op: Op) -> Var {
+        let mut nodes = self.nodes.borrow_mut();
+        nodes.push(Node { value, op, grad: None });
+        Var { tape: self.clone(), index: nodes.len() - 1 }
+    }
+
+    fn value(&self, index: usize) -> Matrix {
+        self.nodes.borrow()[index].value.clone()
+    }
+
+    // walks the tape backwards from `from`, every node it reaches gets its gradient overwritten
+    fn backward(&self, from: usize, seed: Matrix) {
+        let mut nodes = self.nodes.borrow_mut();
+        let mut grads: Vec<Option<Matrix>> = vec![None; from + 1];
+        grads[from] = Some(seed);
+
+        for index in (0..=from).rev() {
+            let grad = match grads[index].clone() {
+                Some(grad) => grad,
+                None => continue
+            };
+            match nodes[index].op {
+                Op::Leaf => (),
+                Op::MatMul(a, b) => {
+                    accumulate(&mut grads, a, grad.clone() * nodes[b].value.t());
+                    accumulate(&mut grads, b, nodes[a].value.t() * grad)
+                },
+                Op::Add(a, b) => {
+                    accumulate(&mut grads, a, unbroadcast(&grad, nodes[a].value.shape()));
+                    accumulate(&mut grads, b, unbroadcast(&grad, nodes[b].value.shape()))
+                },
+                Op::Sub(a, b) => {
+                    accumulate(&mut grads, a, unbroadcast(&grad, nodes[a].value.shape()));
+                    accumulate(&mut grads, b, unbroadcast(&grad.strech(-1.0), nodes[b].value.shape()))
+                },
+                Op::Had(a, b) => {
+                    let shape = grad.shape();
+                    let (val_a, val_b) = (broadcast(&nodes[a].value, shape), broadcast(&nodes[b].value, shape));
+                    accumulate(&mut grads, a, unbroadcast(&grad.had_product(val_b), nodes[a].value.shape()));
+                    accumulate(&mut grads, b, unbroadcast(&grad.had_product(val_a), nodes[b].value.shape()))
+                },
+                Op::Transpose(a) => accumulate(&mut grads, a, grad.t()),
+                Op::Strech(a, times) => accumulate(&mut grads, a, grad.strech(times)),
+                Op::Activate(a, kind) => {
+                    let input = nodes[a].value.clone();
+                    accumulate(&mut grads, a, input.backward(kind, &grad))
+                },
+                Op::Sum(a) => {
+                    let shape = nodes[a].value.shape();
+                    accumulate(&mut grads, a, broadcast(&grad, shape))
+                },
+                Op::Mean(a) => {
+                    let shape = nodes[a].value.shape();
+                    accumulate(&mut grads, a, broadcast(&grad, shape).strech(1.0 / (shape.0 * shape.1) as f64))
+                },
+                Op::RowSum(a) | Op::ColSum(a) => {
+                    let shape = nodes[a].value.shape();
+                    accumulate(&mut grads, a, broadcast(&grad, shape))
+                }
+            }
+        };
+
+        for (node, grad) in nodes.iter_mut().zip(grads) {
+            if grad.is_some() { node.grad = grad }
+        }
+    }
+}
+
+impl Var {
+    pub fn value(&self) -> Matrix {
+        self.tape.value(self.index)
+    }
+
+    pub fn shape(&self) -> (usize, usize) {
+        self.tape.nodes.borrow()[self.index].value.shape()
+    }
+
+    // None until a backward pass has reached this variable
+    pub fn grad(&self) -> Option<Matrix> {
+        self.tape.nodes.borrow()[self.index].grad.clone()
+    }
+
+    pub fn tape(&self) -> Rc<Tape> {
+        self.tape.clone()
+    }
+
+    // d(self)/d(every variable it depends on), self must be a 1x1 matrix
+    pub fn backward(&self) {
+        if self.shape() != (1, 1) { panic!("Only a scalar can start the backward pass, use backward_with!") }
+        self.backward_with(scalar(1.0))
+    }
+
+    // the backward pass seeded with the gradient on self
+    pub fn backward_with(&self, grad: Matrix) {
+        if grad.shape() != self.shape() { panic!("The shapes are not matched!") }
+        self.tape.backward(self.index, grad)
+    }
+
+    fn check_tape(&self, rhs: &Var) {
+        if !Rc::ptr_eq(&self.tape, &rhs.tape) { panic!("The variables are on different tapes!") }
+    }
+
+    fn binary(&self, rhs: &Var, op: Op, value: Matrix) -> Var {
+        self.check_tape(rhs);
+        self.tape.push(value, op)
+    }
+
+    fn broadcast_with(&self, rhs: &Var, f: &dyn Fn(f64, f64) -> f64) -> Matrix {
+        let shape = broadcast_shape(self.shape(), rhs.shape());
+        broadcast(&self.value(), shape).oper_with(broadcast(&rhs.value(), shape), f)
+    }
+
+    pub fn matmul(&self, rhs: &Var) -> Var {
+        self.binary(rhs, Op::MatMul(self.index, rhs.index), self.value() * rhs.value())
+    }
+
+    pub fn had_product(&self, rhs: &Var) -> Var {
+        self.binary(rhs, Op::Had(self.index, rhs.index), self.broadcast_with(rhs, &|i, j| i * j))
+    }
+
+    pub fn t(&self) -> Var {
+        self.tape.push(self.value().t(), Op::Transpose(self.index))
+    }
+
+    pub fn strech(&self, times: f64) -> Var {
+        self.tape.push(self.value().strech(times), Op::Strech(self.index, times))
+    }
+
+    pub fn activate(&self, kind: ActivationKind) -> Var {
+        self.tape.push(self.value().forward(kind), Op::Activate(self.index, kind))
+    }
+
+    pub fn sum(&self) -> Var {
+        let value = self.value();
+        self.tape.push(scalar(value.iter().map(|i| i.sum()).sum()), Op::Sum(self.index))
+    }
+
+    pub fn mean(&self) -> Var {
+        let (x, y) = self.shape();
+        let value = self.value();
+        self.tape.push(scalar(value.iter().map(|i| i.sum()).sum::<f64>() / (x * y) as f64), Op::Mean(self.index))
+    }
+
+    // follows Matrix::sum, a column holding the sum of every row
+    pub fn row_sum(&self) -> Var {
+        let value = Matrix::from_vec(self.value().sum().0.into_iter().map(|i| vec![i]).collect());
+        self.tape.push(value, Op::RowSum(self.index))
+    }
+
+    // a row holding the sum of every column
+    pub fn col_sum(&self) -> Var {
+        let value = Matrix::from_vec(vec![self.value().sum().1]);
+        self.tape.push(value, Op::ColSum(self.index))
+    }
+}
+
+impl std::ops::Add for &Var {
+    type Output = Var;
+    fn add(self, rhs: Self) -> Self::Output {
+        self.binary(rhs, Op::Add(self.index, rhs.index), self.broadcast_with(rhs, &|i, j| i + j))
+    }
+}
+
+impl std::ops::Sub for &Var {
+    type Output = Var;
+    fn sub(self, rhs: Self) -> Self::Output {
+        self.binary(rhs, Op::Sub(self.index, rhs.index), self.broadcast_with(rhs, &|i, j| i - j))
+    }
+}
+
+// matrix multiplication, the same as Mul for Matrix
+impl std::ops::Mul for &Var {
+    type Output = Var;
+    fn mul(self, rhs: Self) -> Self::Output {
+        self.matmul(rhs)
+    }
+}
+
+impl std::fmt::Debug for Var {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Var {{ index: {}, value: {:?} }}", self.index, self.value())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gradcheck::gradcheck;
+    use crate::gradcheck::testing::sample;
+
+    // gradchecks every input of `build`, its output is weighted by fixed values and summed,
+    // so every element of it gets a different gradient
+    fn check(name: &str, inputs: &[Matrix], build: impl Fn(&[Var]) -> Var) {
+        let run = |values: &[Matrix]| {
+            let tape = Tape::new();
+            let vars = values.iter().map(|i| tape.var(i.clone())).collect::<Vec<_>>();
+            let out = build(&vars);
+            let weights = tape.var(sample(out.shape(), 99, 1.0));
+            (vars, out.had_product(&weights).sum())
+        };
+        let (vars, loss) = run(inputs);
+        loss.backward();
+        for (index, var) in vars.iter().enumerate() {
+            let f = |m: &Matrix| {
+                let mut values = inputs.to_vec();
+                values[index] = m.clone();
+                run(&values).1.value().get(0, 0)
+            };
+            let check = gradcheck(f, &inputs[index], &var.grad().expect("The input has no gradient!"));
+            assert!(check.passed(1e-5, 1e-7), "{} input {}: {}", name, index, check)
+        }
+    }
+
+    #[test]
+    fn matmul() {
+        check("matmul", &[sample((3, 2), 1, 1.0), sample((4, 3), 2, 1.0)], |v| v[0].matmul(&v[1]));
+        check("mul", &[sample((2, 2), 3, 1.0), sample((1, 2), 4, 1.0)], |v| &v[0] * &v[1])
+    }
+
+    #[test]
+    fn add_and_sub_broadcast() {
+        let shapes = [((4, 3), (4, 3)), ((4, 3), (1, 3)), ((4, 3), (4, 1)), ((4, 3), (1, 1)), ((4, 1), (1, 3)), ((1, 1), (2, 3))];
+        for (a, b) in shapes {
+            let inputs = [sample(a, 5, 1.0), sample(b, 6, 1.0)];
+            check(&format!("add {:?} {:?}", a, b), &inputs, |v| &v[0] + &v[1]);
+            check(&format!("sub {:?} {:?}", a, b), &inputs, |v| &v[0] - &v[1])
+        }
+    }
+
+    #[test]
+    fn had_product() {
+        check("had", &[sample((4, 3), 7, 1.0), sample((4, 3), 8, 1.0)], |v| v[0].had_product(&v[1]));
+        check("had broadcast", &[sample((4, 3), 7, 1.0), sample((1, 3), 8, 1.0)], |v| v[0].had_product(&v[1]))
+    }
+
+    #[test]
+    fn transpose_and_strech() {
+        check("t", &[sample((4, 3), 9, 1.0)], |v| v[0].t());
+        check("strech", &[sample((4, 3), 10, 1.0)], |v| v[0].strech(-1.5))
+    }
+
+    #[test]
+    fn activations() {
+        let kinds = [
+            ActivationKind::Identity, ActivationKind::Sigmoid, ActivationKind::Tanh, ActivationKind::LeakyRelu(0.1),
+            ActivationKind::Elu(1.0), ActivationKind::Gelu, ActivationKind::Swish(1.5), ActivationKind::Softplus,
+            ActivationKind::Mish, ActivationKind::Softmax
+        ];
+        for kind in kinds {
+            check(&format!("{}", kind), &[sample((4, 3), 11, 2.0)], |v| v[0].activate(kind))
+        }
+    }
+
+    #[test]
+    fn reductions() {
+        let input = [sample((4, 3), 12, 1.0)];
+        check("sum", &input, |v| v[0].sum());
+        check("mean", &input, |v| v[0].mean());
+        check("row_sum", &input, |v| v[0].row_sum());
+        check("col_sum", &input, |v| v[0].col_sum());
+        let tape = Tape::new();
+        let x = tape.var(Matrix::from([[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]));
+        crate::assert_matrix_close!(x.row_sum().value(), Matrix::from([[6.0], [15.0]]));
+        crate::assert_matrix_close!(x.col_sum().value(), Matrix::from([[5.0, 7.0, 9.0]]));
+        assert_eq!(x.mean().value().get(0, 0), 3.5)
+    }
+
+    #[test]
+    fn reused_variables_accumulate() {
+        let input = [sample((3, 3), 13, 1.0)];
+        check("x * x + 3x", &input, |v| &v[0].had_product(&v[0]) + &v[0].strech(3.0));
+        check("x^T x", &input, |v| v[0].t().matmul(&v[0]));
+        // d(sum(x + x))/dx is 2 everywhere
+        let tape = Tape::new();
+        let x = tape.var(Matrix::from([[1.0, -2.0]]));
+        (&x + &x).sum().backward();
+        crate::assert_matrix_close!(x.grad().unwrap(), Matrix::from([[2.0, 2.0]]))
+    }
+
+    #[test]
+    fn two_layer_network() {
+        // softmax(w2 tanh(w1 x + b1)), with the bias broadcast over the sample columns
+        let inputs = [sample((5, 4), 14, 1.0), sample((4, 3), 15, 0.8), sample((1, 3), 16, 0.3), sample((3, 2), 17, 0.8)];
+        check("network", &inputs, |v| {
+            let hidden = (&v[1].matmul(&v[0]) + &v[2]).activate(ActivationKind::Tanh);
+            v[3].matmul(&hidden).activate(ActivationKind::Softmax)
+        });
+        // a variable the output does not depend on gets no gradient
+        let tape = Tape::new();
+        let (x, unused) = (tape.var(Matrix::from([[1.0]])), tape.var(Matrix::from([[2.0]])));
+        x.strech(2.0).backward();
+        assert!(unused.grad().is_none())
+    }
+}
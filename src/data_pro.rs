@@ -1,6 +1,6 @@
 use polars::prelude::{self, SerReader};
 use std::fs::File;
-use super::lin_alg::{Vector, Matrix};
+use super::lin_alg::Matrix;
 
 pub struct DataSet {
     val: Matrix,
@@ -8,10 +8,16 @@ pub struct DataSet {
 }
 
 impl DataSet {
+    // one sample per row of `val`, with its label at the same index
+    pub fn new(val: Matrix, label: Vec<i64>) -> Self {
+        if val.shape().1 != label.len() { panic!("Every sample needs a label!") }
+        Self { val, label }
+    }
+
     pub fn read_from(path: &str) -> Self{
         let mut label: Vec<i64> = Vec::new();
         let mut buf: Vec<Vec<i64>> = Vec::new();
-        let file = File::open(path.to_string()).expect("Cannot find the file");
+        let file = File::open(path).expect("Cannot find the file");
         let csv = prelude::CsvReader::new(file)
             .infer_schema(None)
             .has_header(true)
@@ -52,6 +58,23 @@ impl DataSet {
         self.label.len()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.label.is_empty()
+    }
+
+    // the samples at `indexes` turned into one sample per column, the layout Sequential takes
+    pub fn batch(&self, indexes: &[usize]) -> (Matrix, Vec<i64>) {
+        let rows = self.val.iter().collect::<Vec<_>>();
+        let mut cols = Vec::new();
+        let mut label = Vec::new();
+        for i in indexes.iter() {
+            if *i >= rows.len() { panic!("The index is out of the dataset!") }
+            cols.push(rows[*i].get_val().clone());
+            label.push(self.label[*i])
+        };
+        (Matrix::from_ver_vec(cols), label)
+    }
+
     // pub fn iter_batch(&self, size: usize, keep_remain: bool) -> std::slice::Iter<'_, Self> {
         
     // }
@@ -61,4 +84,30 @@ impl std::fmt::Display for DataSet {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "label: {:?}", self.label)
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dataset() -> DataSet {
+        DataSet::new(Matrix::from([[1.0, 2.0], [3.0, 4.0], [5.0, 6.0]]), vec![7, 8, 9])
+    }
+
+    #[test]
+    fn batch_puts_one_sample_per_column() {
+        let (batch, label) = dataset().batch(&[2, 0]);
+        assert_eq!(batch.shape(), (2, 2));
+        crate::assert_matrix_close!(batch, Matrix::from([[5.0, 1.0], [6.0, 2.0]]));
+        assert_eq!(label, vec![9, 7]);
+        // repeated indexes are allowed, the labels keep the order of the indexes
+        let (batch, label) = dataset().batch(&[1, 1, 0]);
+        crate::assert_matrix_close!(batch, Matrix::from([[3.0, 3.0, 1.0], [4.0, 4.0, 2.0]]));
+        assert_eq!(label, vec![8, 8, 7])
+    }
+
+    #[test]
+    #[should_panic(expected = "The index is out of the dataset!")]
+    fn batch_index_out_of_range() {
+        dataset().batch(&[0, 3]);
+    }
+}
@@ -0,0 +1,235 @@
+use crate::func::{Activation, ActivationKind, Real};
+use crate::lin_alg::{Matrix, Vector};
+
+// a + bε with ε² = 0, carrying a value and its exact derivative through every operation
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Dual {
+    pub val: f64,
+    pub der: f64
+}
+
+impl Dual {
+    pub fn new(val: f64, der: f64) -> Self {
+        Self { val, der }
+    }
+
+    pub fn constant(val: f64) -> Self {
+        Self { val, der: 0.0 }
+    }
+
+    // the variable being differentiated by, dx/dx = 1
+    pub fn variable(val: f64) -> Self {
+        Self { val, der: 1.0 }
+    }
+
+    pub fn exp(self) -> Self {
+        let exp = self.val.exp();
+        Self::new(exp, exp * self.der)
+    }
+
+    pub fn exp_m1(self) -> Self {
+        Self::new(self.val.exp_m1(), self.val.exp() * self.der)
+    }
+
+    pub fn ln(self) -> Self {
+        Self::new(self.val.ln(), self.der / self.val)
+    }
+
+    pub fn ln_1p(self) -> Self {
+        Self::new(self.val.ln_1p(), self.der / (1.0 + self.val))
+    }
+
+    pub fn sqrt(self) -> Self {
+        let sqrt = self.val.sqrt();
+        Self::new(sqrt, self.der / (2.0 * sqrt))
+    }
+
+    pub fn powi(self, n: i32) -> Self {
+        Self::new(self.val.powi(n), n as f64 * self.val.powi(n - 1) * self.der)
+    }
+
+    pub fn powf(self, n: f64) -> Self {
+        Self::new(self.val.powf(n), n * self.val.powf(n - 1.0) * self.der)
+    }
+
+    pub fn sin(self) -> Self {
+        Self::new(self.val.sin(), self.val.cos() * self.der)
+    }
+
+    pub fn cos(self) -> Self {
+        Self::new(self.val.cos(), -self.val.sin() * self.der)
+    }
+
+    pub fn tanh(self) -> Self {
+        let tanh = self.val.tanh();
+        Self::new(tanh, (1.0 - tanh * tanh) * self.der)
+    }
+
+    // the derivative is taken as 0 at the kink
+    pub fn abs(self) -> Self {
+        let sign = if self.val > 0.0 { 1.0 } else if self.val < 0.0 { -1.0 } else { 0.0 };
+        Self::new(self.val.abs(), sign * self.der)
+    }
+}
+
+impl From<f64> for Dual {
+    fn from(val: f64) -> Self {
+        Self::constant(val)
+    }
+}
+
+impl std::fmt::Display for Dual {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} + {}ε", self.val, self.der)
+    }
+}
+
+impl std::ops::Add for Dual {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self::Output {
+        Self::new(self.val + rhs.val, self.der + rhs.der)
+    }
+}
+
+impl std::ops::Sub for Dual {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self::new(self.val - rhs.val, self.der - rhs.der)
+    }
+}
+
+impl std::ops::Mul for Dual {
+    type Output = Self;
+    fn mul(self, rhs: Self) -> Self::Output {
+        Self::new(self.val * rhs.val, self.der * rhs.val + self.val * rhs.der)
+    }
+}
+
+impl std::ops::Div for Dual {
+    type Output = Self;
+    fn div(self, rhs: Self) -> Self::Output {
+        Self::new(self.val / rhs.val, (self.der * rhs.val - self.val * rhs.der) / (rhs.val * rhs.val))
+    }
+}
+
+impl std::ops::Neg for Dual {
+    type Output = Self;
+    fn neg(self) -> Self::Output {
+        Self::new(-self.val, -self.der)
+    }
+}
+
+impl Real for Dual {
+    fn val(self) -> f64 {
+        self.val
+    }
+
+    fn exp(self) -> Self {
+        Dual::exp(self)
+    }
+
+    fn exp_m1(self) -> Self {
+        Dual::exp_m1(self)
+    }
+
+    fn ln_1p(self) -> Self {
+        Dual::ln_1p(self)
+    }
+
+    fn tanh(self) -> Self {
+        Dual::tanh(self)
+    }
+
+    fn abs(self) -> Self {
+        Dual::abs(self)
+    }
+}
+
+// forward runs the activation's own formula on dual numbers, so its `der` never touches the hand-written derivative
+impl Activation for Dual {
+    fn forward(&self, kind: ActivationKind) -> Self {
+        kind.apply(*self)
+    }
+
+    fn derivative(&self, kind: ActivationKind) -> Self {
+        if !kind.is_elementwise() { panic!("Softmax has no element-wise derivative, use backward!") }
+        kind.derivative(*self)
+    }
+
+    fn backward(&self, kind: ActivationKind, grad: &Self) -> Self {
+        kind.derivative(*self) * *grad
+    }
+}
+
+// f'(x) of a scalar function
+pub fn derivative(f: impl Fn(Dual) -> Dual, x: f64) -> f64 {
+    f(Dual::variable(x)).der
+}
+
+// the m x n Jacobian of f: R^n -> R^m with one forward pass per input, row i holds the gradient of output i
+pub fn jacobian(f: impl Fn(&[Dual]) -> Vec<Dual>, x: &Vector) -> Matrix {
+    let mut cols = Vec::new();
+    for j in 0..x.len() {
+        let input = x.iter().enumerate()
+            .map(|(i, val)| if i == j { Dual::variable(*val) } else { Dual::constant(*val) })
+            .collect::<Vec<_>>();
+        cols.push(f(&input).iter().map(|i| i.der).collect::<Vec<_>>())
+    };
+    if cols.is_empty() || cols[0].is_empty() { panic!("The Jacobian is empty!") }
+    Matrix::from_ver_vec(cols)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn derivative_of_known_functions() {
+        // d/dx x^3 sin(x) = 3x^2 sin(x) + x^3 cos(x)
+        let x: f64 = 1.3;
+        let expected = 3.0 * x * x * x.sin() + x * x * x * x.cos();
+        assert!((derivative(|x| x.powi(3) * x.sin(), x) - expected).abs() < 1e-12);
+        // d/dx ln(x) / sqrt(x) = (2 - ln(x)) / (2 x^1.5)
+        let expected = (2.0 - x.ln()) / (2.0 * x.powf(1.5));
+        assert!((derivative(|x| x.ln() / x.sqrt(), x) - expected).abs() < 1e-12)
+    }
+
+    #[test]
+    fn activation_derivatives_match_dual_numbers() {
+        let kinds = [
+            ActivationKind::Identity, ActivationKind::Sigmoid, ActivationKind::Tanh, ActivationKind::Relu,
+            ActivationKind::LeakyRelu(0.1), ActivationKind::Elu(0.5), ActivationKind::Selu, ActivationKind::Gelu,
+            ActivationKind::Swish(1.5), ActivationKind::Softplus, ActivationKind::Mish, ActivationKind::HardSigmoid,
+            ActivationKind::Softmax
+        ];
+        // the kinks at 0 and ±3 take the same side as the hand-written derivatives
+        let points = [-40.0, -3.0, -2.2, -0.7, -1e-3, 0.0, 1e-3, 0.4, 1.5, 3.0, 40.0];
+        for kind in kinds {
+            for x in points {
+                let (dual, analytic) = (derivative(|x| kind.apply(x), x), kind.derivative(x));
+                assert!((dual - analytic).abs() <= 1e-12 * analytic.abs().max(1.0), "{} at {}: dual {}, analytic {}", kind, x, dual, analytic)
+            }
+        }
+    }
+
+    #[test]
+    fn jacobian_of_a_vector_function() {
+        // (x y, x sin(z), x + y^2)
+        let f = |v: &[Dual]| vec![v[0] * v[1], v[0] * v[2].sin(), v[0] + v[1] * v[1]];
+        let (x, y, z): (f64, f64, f64) = (1.5, 2.0, 0.7);
+        let expected = Matrix::from([
+            [y, x, 0.0],
+            [z.sin(), 0.0, x * z.cos()],
+            [1.0, 2.0 * y, 0.0]
+        ]);
+        crate::assert_matrix_close!(jacobian(f, &Vector::from([x, y, z])), expected);
+        // polar to cartesian, 2 outputs from 2 inputs with determinant r
+        let polar = |v: &[Dual]| vec![v[0] * v[1].cos(), v[0] * v[1].sin()];
+        let (r, angle): (f64, f64) = (2.0, 0.3);
+        let expected = Matrix::from([[angle.cos(), -r * angle.sin()], [angle.sin(), r * angle.cos()]]);
+        crate::assert_matrix_close!(jacobian(polar, &Vector::from([r, angle])), expected);
+        // more outputs than inputs
+        let out = jacobian(|v: &[Dual]| vec![v[0], v[0] * v[0], v[0].exp()], &Vector::from([0.5]));
+        crate::assert_matrix_close!(out, Matrix::from([[1.0], [1.0], [0.5f64.exp()]]))
+    }
+}
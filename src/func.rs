@@ -1,3 +1,29 @@
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ActivationKind {
+    Identity,
+    Sigmoid,
+    Tanh,
+    Relu,
+    LeakyRelu(f64),
+    Elu(f64),
+    Selu,
+    Gelu,
+    Swish(f64),
+    Softplus,
+    Mish,
+    HardSigmoid,
+    // the only one that isn't element-wise, it normalises every Vector / Matrix column
+    Softmax
+}
+
+pub trait Activation: Sized {
+    fn forward(&self, kind: ActivationKind) -> Self;
+    // the element-wise derivative at self, Softmax panics since its Jacobian isn't diagonal
+    fn derivative(&self, kind: ActivationKind) -> Self;
+    // the gradient on self, given the gradient on forward(self), works for every kind
+    fn backward(&self, kind: ActivationKind, grad: &Self) -> Self;
+}
+
 pub trait Sigmoid {
     fn sig_for(&self) -> Self;
     fn sig_back(&self) -> Self;
@@ -15,39 +41,511 @@ pub trait Relu {
 
 pub trait Softmax {
     fn soft_for(&self) -> Self;
+    // softmax(x / temperature), flatter above 1 and sharper below
+    fn soft_for_temp(&self, temperature: f64) -> Self;
+    fn log_soft_for(&self) -> Self;
+    // the gradient on the logits `self`, given the gradient on the softmax output
+    fn soft_back(&self, grad: &Self) -> Self;
 }
 
-impl Sigmoid for f64 {
-    fn sig_back(&self) -> Self {
-        let buf = self.sig_for();
-        return buf * (1.0 - buf)
+pub trait LeakyRelu {
+    fn leaky_for(&self, alpha: f64) -> Self;
+    fn leaky_back(&self, alpha: f64) -> Self;
+}
+
+pub trait Elu {
+    fn elu_for(&self, alpha: f64) -> Self;
+    fn elu_back(&self, alpha: f64) -> Self;
+}
+
+pub trait Selu {
+    fn selu_for(&self) -> Self;
+    fn selu_back(&self) -> Self;
+}
+
+pub trait Gelu {
+    fn gelu_for(&self) -> Self;
+    fn gelu_back(&self) -> Self;
+}
+
+pub trait Swish {
+    fn swish_for(&self, beta: f64) -> Self;
+    fn swish_back(&self, beta: f64) -> Self;
+}
+
+pub trait Softplus {
+    fn softplus_for(&self) -> Self;
+    fn softplus_back(&self) -> Self;
+}
+
+pub trait Mish {
+    fn mish_for(&self) -> Self;
+    fn mish_back(&self) -> Self;
+}
+
+pub trait HardSigmoid {
+    fn hard_sig_for(&self) -> Self;
+    fn hard_sig_back(&self) -> Self;
+}
+
+// the constants of Klambauer et al. (2017) that keep activations normalised
+pub const SELU_ALPHA: f64 = 1.6732632423543772;
+pub const SELU_LAMBDA: f64 = 1.0507009873554805;
+// the slope LeakyRelu gets when parsed without one, the same as PyTorch
+pub const LEAKY_ALPHA: f64 = 0.01;
+
+// the tanh approximation, 0.5x(1 + tanh(sqrt(2/pi)(x + 0.044715x^3)))
+const GELU_C: f64 = 0.7978845608028654;
+const GELU_K: f64 = 0.044715;
+
+// what the activations need from a number, so they run on dual numbers as well as f64
+pub trait Real: Copy + From<f64>
+    + std::ops::Add<Output = Self> + std::ops::Sub<Output = Self>
+    + std::ops::Mul<Output = Self> + std::ops::Div<Output = Self> + std::ops::Neg<Output = Self> {
+    fn val(self) -> f64;
+    fn exp(self) -> Self;
+    fn exp_m1(self) -> Self;
+    fn ln_1p(self) -> Self;
+    fn tanh(self) -> Self;
+    fn abs(self) -> Self;
+}
+
+impl Real for f64 {
+    fn val(self) -> f64 {
+        self
+    }
+
+    fn exp(self) -> Self {
+        f64::exp(self)
     }
 
+    fn exp_m1(self) -> Self {
+        f64::exp_m1(self)
+    }
+
+    fn ln_1p(self) -> Self {
+        f64::ln_1p(self)
+    }
+
+    fn tanh(self) -> Self {
+        f64::tanh(self)
+    }
+
+    fn abs(self) -> Self {
+        f64::abs(self)
+    }
+}
+
+fn sigmoid<T: Real>(x: T) -> T {
+    // 1 / (1 + e^-x)
+    T::from(1.0) / (T::from(1.0) + (-x).exp())
+}
+
+// log(1 + e^x) written as max(x, 0) + log(1 + e^-|x|) so it never overflows, one branch
+// for each sign instead of abs(), which would lose the derivative at 0 for dual numbers
+fn softplus<T: Real>(x: T) -> T {
+    if x.val() > 0.0 { x + (-x).exp().ln_1p() } else { x.exp().ln_1p() }
+}
+
+impl ActivationKind {
+    pub fn apply<T: Real>(&self, x: T) -> T {
+        let c = |val: f64| T::from(val);
+        match *self {
+            ActivationKind::Identity => x,
+            ActivationKind::Sigmoid => sigmoid(x),
+            // (e^x - e^-x) / (e^x + e^-x), without overflowing for large |x|
+            ActivationKind::Tanh => x.tanh(),
+            ActivationKind::Relu => if x.val() > 0.0 { x } else { c(0.0) },
+            ActivationKind::LeakyRelu(alpha) => if x.val() > 0.0 { x } else { c(alpha) * x },
+            // alpha * (e^x - 1) below zero
+            ActivationKind::Elu(alpha) => if x.val() > 0.0 { x } else { c(alpha) * x.exp_m1() },
+            ActivationKind::Selu => c(SELU_LAMBDA) * ActivationKind::Elu(SELU_ALPHA).apply(x),
+            ActivationKind::Gelu => c(0.5) * x * (c(1.0) + (c(GELU_C) * (x + c(GELU_K) * x * x * x)).tanh()),
+            // x * sigmoid(beta * x), SiLU when beta is 1
+            ActivationKind::Swish(beta) => x * sigmoid(c(beta) * x),
+            ActivationKind::Softplus => softplus(x),
+            // x * tanh(softplus(x))
+            ActivationKind::Mish => x * softplus(x).tanh(),
+            // clamp(x / 6 + 1 / 2, 0, 1), the same as PyTorch
+            ActivationKind::HardSigmoid => {
+                if x.val() <= -3.0 { c(0.0) } else if x.val() >= 3.0 { c(1.0) } else { x / c(6.0) + c(0.5) }
+            },
+            // a single logit always gets all of the probability
+            ActivationKind::Softmax => c(1.0)
+        }
+    }
+
+    pub fn derivative<T: Real>(&self, x: T) -> T {
+        let c = |val: f64| T::from(val);
+        match *self {
+            ActivationKind::Identity => c(1.0),
+            ActivationKind::Sigmoid => {
+                let buf = sigmoid(x);
+                buf * (c(1.0) - buf)
+            },
+            ActivationKind::Tanh => {
+                let buf = x.tanh();
+                c(1.0) - buf * buf
+            },
+            ActivationKind::Relu => if x.val() > 0.0 { c(1.0) } else { c(0.0) },
+            ActivationKind::LeakyRelu(alpha) => if x.val() > 0.0 { c(1.0) } else { c(alpha) },
+            ActivationKind::Elu(alpha) => if x.val() > 0.0 { c(1.0) } else { c(alpha) * x.exp() },
+            ActivationKind::Selu => c(SELU_LAMBDA) * ActivationKind::Elu(SELU_ALPHA).derivative(x),
+            ActivationKind::Gelu => {
+                let t = (c(GELU_C) * (x + c(GELU_K) * x * x * x)).tanh();
+                c(0.5) * (c(1.0) + t) + c(0.5) * x * (c(1.0) - t * t) * c(GELU_C) * (c(1.0) + c(3.0 * GELU_K) * x * x)
+            },
+            ActivationKind::Swish(beta) => {
+                let sig = sigmoid(c(beta) * x);
+                sig + c(beta) * x * sig * (c(1.0) - sig)
+            },
+            ActivationKind::Softplus => sigmoid(x),
+            ActivationKind::Mish => {
+                let t = softplus(x).tanh();
+                t + x * (c(1.0) - t * t) * sigmoid(x)
+            },
+            ActivationKind::HardSigmoid => if x.val() > -3.0 && x.val() < 3.0 { c(1.0 / 6.0) } else { c(0.0) },
+            ActivationKind::Softmax => c(0.0)
+        }
+    }
+
+    pub fn is_elementwise(&self) -> bool {
+        *self != ActivationKind::Softmax
+    }
+}
+
+impl std::fmt::Display for ActivationKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ActivationKind::Identity => write!(f, "identity"),
+            ActivationKind::Sigmoid => write!(f, "sigmoid"),
+            ActivationKind::Tanh => write!(f, "tanh"),
+            ActivationKind::Relu => write!(f, "relu"),
+            ActivationKind::LeakyRelu(alpha) => write!(f, "leaky_relu({})", alpha),
+            ActivationKind::Elu(alpha) => write!(f, "elu({})", alpha),
+            ActivationKind::Selu => write!(f, "selu"),
+            ActivationKind::Gelu => write!(f, "gelu"),
+            ActivationKind::Swish(beta) => write!(f, "swish({})", beta),
+            ActivationKind::Softplus => write!(f, "softplus"),
+            ActivationKind::Mish => write!(f, "mish"),
+            ActivationKind::HardSigmoid => write!(f, "hard_sigmoid"),
+            ActivationKind::Softmax => write!(f, "softmax")
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ActivationParseError(pub String);
+
+impl std::fmt::Display for ActivationParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "unknown activation `{}`", self.0)
+    }
+}
+
+impl std::error::Error for ActivationParseError {}
+
+// case-insensitive names like "relu", "leaky-relu" or "elu(0.5)", the parameter is optional
+impl std::str::FromStr for ActivationKind {
+    type Err = ActivationParseError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || ActivationParseError(s.to_string());
+        let text = s.trim().to_lowercase();
+        let (name, param) = match text.split_once('(') {
+            Some((name, rest)) => {
+                let param = rest.strip_suffix(')').ok_or_else(error)?;
+                (name.trim(), Some(param.trim().parse::<f64>().map_err(|_| error())?))
+            },
+            None => (text.as_str(), None)
+        };
+        // only the name, a parameter can be negative
+        let name = name.replace('-', "_");
+
+        let kind = match (name.as_str(), param) {
+            ("identity" | "linear", None) => ActivationKind::Identity,
+            ("sigmoid", None) => ActivationKind::Sigmoid,
+            ("tanh", None) => ActivationKind::Tanh,
+            ("relu", None) => ActivationKind::Relu,
+            ("leaky_relu" | "leakyrelu", alpha) => ActivationKind::LeakyRelu(alpha.unwrap_or(LEAKY_ALPHA)),
+            ("elu", alpha) => ActivationKind::Elu(alpha.unwrap_or(1.0)),
+            ("selu", None) => ActivationKind::Selu,
+            ("gelu", None) => ActivationKind::Gelu,
+            ("swish", beta) => ActivationKind::Swish(beta.unwrap_or(1.0)),
+            ("silu", None) => ActivationKind::Swish(1.0),
+            ("softplus", None) => ActivationKind::Softplus,
+            ("mish", None) => ActivationKind::Mish,
+            ("hard_sigmoid" | "hardsigmoid", None) => ActivationKind::HardSigmoid,
+            ("softmax", None) => ActivationKind::Softmax,
+            _ => return Err(error())
+        };
+        Ok(kind)
+    }
+}
+
+impl Activation for f64 {
+    fn forward(&self, kind: ActivationKind) -> Self {
+        kind.apply(*self)
+    }
+
+    fn derivative(&self, kind: ActivationKind) -> Self {
+        if !kind.is_elementwise() { panic!("Softmax has no element-wise derivative, use backward!") }
+        kind.derivative(*self)
+    }
+
+    fn backward(&self, kind: ActivationKind, grad: &Self) -> Self {
+        kind.derivative(*self) * grad
+    }
+}
+
+// the per-activation traits are thin wrappers around Activation
+
+impl<T: Activation> Sigmoid for T {
     fn sig_for(&self) -> Self {
-        return 1.0 / (1.0 + (-self).exp())
-        // 1 / (1 + e^-x)
+        self.forward(ActivationKind::Sigmoid)
+    }
+
+    fn sig_back(&self) -> Self {
+        self.derivative(ActivationKind::Sigmoid)
+    }
+}
+
+impl<T: Activation> Tanh for T {
+    fn tanh_for(&self) -> Self {
+        self.forward(ActivationKind::Tanh)
+    }
+
+    fn tanh_back(&self) -> Self {
+        self.derivative(ActivationKind::Tanh)
     }
 }
 
-impl Relu for f64 {
+impl<T: Activation> Relu for T {
     fn relu_for(&self) -> Self {
-        return if self > &0.0 { *self } else { 0.0 }
+        self.forward(ActivationKind::Relu)
     }
 
     fn relu_back(&self) -> Self {
-        return if self > &0.0 { 1.0 } else { 0.0 }
+        self.derivative(ActivationKind::Relu)
     }
 }
 
-impl Tanh for f64 {
-    fn tanh_for(&self) -> Self {
-        let exp = self.exp();
-        let exp_ne = (-self).exp();
-        return (exp + exp_ne) / (exp - exp_ne)
+impl<T: Activation> LeakyRelu for T {
+    fn leaky_for(&self, alpha: f64) -> Self {
+        self.forward(ActivationKind::LeakyRelu(alpha))
     }
 
-    fn tanh_back(&self) -> Self {
-        let buf = self.tanh_for();
-        return 1.0 - buf * buf
+    fn leaky_back(&self, alpha: f64) -> Self {
+        self.derivative(ActivationKind::LeakyRelu(alpha))
+    }
+}
+
+impl<T: Activation> Elu for T {
+    fn elu_for(&self, alpha: f64) -> Self {
+        self.forward(ActivationKind::Elu(alpha))
+    }
+
+    fn elu_back(&self, alpha: f64) -> Self {
+        self.derivative(ActivationKind::Elu(alpha))
+    }
+}
+
+impl<T: Activation> Selu for T {
+    fn selu_for(&self) -> Self {
+        self.forward(ActivationKind::Selu)
+    }
+
+    fn selu_back(&self) -> Self {
+        self.derivative(ActivationKind::Selu)
+    }
+}
+
+impl<T: Activation> Gelu for T {
+    fn gelu_for(&self) -> Self {
+        self.forward(ActivationKind::Gelu)
+    }
+
+    fn gelu_back(&self) -> Self {
+        self.derivative(ActivationKind::Gelu)
+    }
+}
+
+impl<T: Activation> Swish for T {
+    fn swish_for(&self, beta: f64) -> Self {
+        self.forward(ActivationKind::Swish(beta))
+    }
+
+    fn swish_back(&self, beta: f64) -> Self {
+        self.derivative(ActivationKind::Swish(beta))
+    }
+}
+
+impl<T: Activation> Softplus for T {
+    fn softplus_for(&self) -> Self {
+        self.forward(ActivationKind::Softplus)
+    }
+
+    fn softplus_back(&self) -> Self {
+        self.derivative(ActivationKind::Softplus)
+    }
+}
+
+impl<T: Activation> Mish for T {
+    fn mish_for(&self) -> Self {
+        self.forward(ActivationKind::Mish)
     }
-}
\ No newline at end of file
+
+    fn mish_back(&self) -> Self {
+        self.derivative(ActivationKind::Mish)
+    }
+}
+
+impl<T: Activation> HardSigmoid for T {
+    fn hard_sig_for(&self) -> Self {
+        self.forward(ActivationKind::HardSigmoid)
+    }
+
+    fn hard_sig_back(&self) -> Self {
+        self.derivative(ActivationKind::HardSigmoid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lin_alg::{Matrix, Vector};
+
+    #[test]
+    fn sigmoid_values() {
+        let out = Vector::from([-2.0, 0.0, 1.0]).sig_for();
+        crate::assert_vector_close!(out, Vector::from([0.11920292202211755, 0.5, 0.7310585786300049]))
+    }
+
+    #[test]
+    fn tanh_values() {
+        let out = Vector::from([-2.0, 0.0, 1.0]).tanh_for();
+        crate::assert_vector_close!(out, Vector::from([-0.9640275800758169, 0.0, 0.7615941559557649]));
+        crate::assert_vector_close!(Vector::from([0.0, 1.0]).tanh_back(), Vector::from([1.0, 0.41997434161402614]))
+    }
+
+    #[test]
+    fn relu_values() {
+        let matrix = Matrix::from([[-2.0, 0.0], [1.5, -0.5]]);
+        crate::assert_matrix_close!(matrix.relu_for(), Matrix::from([[0.0, 0.0], [1.5, 0.0]]));
+        crate::assert_matrix_close!(matrix.relu_back(), Matrix::from([[0.0, 0.0], [1.0, 0.0]]))
+    }
+
+    const POINTS: [f64; 11] = [-3.5, -2.0, -1.0, -0.5, -1e-3, 0.0, 1e-3, 0.5, 1.0, 2.0, 3.5];
+
+    // compares back(x) with central differences of forward, at a kink x = 0 the derivative takes
+    // the left slope so it is compared with a backward difference instead
+    fn check(name: &str, kinked: bool, forward: impl Fn(f64) -> f64, back: impl Fn(f64) -> f64) {
+        for x in POINTS {
+            let (numeric, tol) = if kinked && x == 0.0 {
+                let h = 1e-7;
+                ((forward(x) - forward(x - h)) / h, 1e-5)
+            } else {
+                let h = 1e-6;
+                ((forward(x + h) - forward(x - h)) / (2.0 * h), 1e-6)
+            };
+            let analytic = back(x);
+            if (analytic - numeric).abs() > tol {
+                panic!("{} at {}: analytic {}, numeric {}", name, x, analytic, numeric)
+            }
+        }
+    }
+
+    #[test]
+    fn derivatives_match_finite_differences() {
+        check("sigmoid", false, |x| x.sig_for(), |x| x.sig_back());
+        check("tanh", false, |x| x.tanh_for(), |x| x.tanh_back());
+        check("relu", true, |x| x.relu_for(), |x| x.relu_back());
+        check("leaky_relu", true, |x| x.leaky_for(0.1), |x| x.leaky_back(0.1));
+        check("elu", true, |x| x.elu_for(0.5), |x| x.elu_back(0.5));
+        check("elu(1)", false, |x| x.elu_for(1.0), |x| x.elu_back(1.0));
+        check("selu", true, |x| x.selu_for(), |x| x.selu_back());
+        check("gelu", false, |x| x.gelu_for(), |x| x.gelu_back());
+        check("swish", false, |x| x.swish_for(1.5), |x| x.swish_back(1.5));
+        check("softplus", false, |x| x.softplus_for(), |x| x.softplus_back());
+        check("mish", false, |x| x.mish_for(), |x| x.mish_back());
+        check("hard_sigmoid", false, |x| x.hard_sig_for(), |x| x.hard_sig_back())
+    }
+
+    #[test]
+    fn kink_conventions() {
+        assert_eq!(0.0.relu_back(), 0.0);
+        assert_eq!(0.0.leaky_back(0.1), 0.1);
+        assert_eq!(0.0.elu_back(0.5), 0.5);
+        assert_eq!((-3.0).hard_sig_back(), 0.0);
+        assert_eq!(3.0.hard_sig_back(), 0.0)
+    }
+
+    #[test]
+    fn vector_and_matrix_match_f64() {
+        let kinds = [
+            ActivationKind::Sigmoid, ActivationKind::Tanh, ActivationKind::Relu, ActivationKind::LeakyRelu(0.1),
+            ActivationKind::Elu(0.5), ActivationKind::Selu, ActivationKind::Gelu, ActivationKind::Swish(1.5),
+            ActivationKind::Softplus, ActivationKind::Mish, ActivationKind::HardSigmoid
+        ];
+        let vector = Vector::from(POINTS);
+        let matrix = Matrix::from([[-2.0, -0.5, 0.0], [1e-3, 1.0, 3.5]]);
+        for kind in kinds {
+            let map = |f: &dyn Fn(f64) -> f64| Vector::from_vec(POINTS.iter().map(|i| f(*i)).collect());
+            crate::assert_vector_close!(vector.forward(kind), map(&|x| x.forward(kind)));
+            crate::assert_vector_close!(vector.derivative(kind), map(&|x| x.derivative(kind)));
+            crate::assert_matrix_close!(matrix.forward(kind), matrix.oper(&|x| x.forward(kind)));
+            crate::assert_matrix_close!(matrix.derivative(kind), matrix.oper(&|x| x.derivative(kind)))
+        }
+    }
+
+    const KINDS: [ActivationKind; 13] = [
+        ActivationKind::Identity, ActivationKind::Sigmoid, ActivationKind::Tanh, ActivationKind::Relu,
+        ActivationKind::LeakyRelu(0.2), ActivationKind::Elu(0.5), ActivationKind::Selu, ActivationKind::Gelu,
+        ActivationKind::Swish(1.5), ActivationKind::Softplus, ActivationKind::Mish, ActivationKind::HardSigmoid,
+        ActivationKind::Softmax
+    ];
+
+    #[test]
+    fn parse_names() {
+        for text in ["relu", "ReLU", " RELU "] {
+            assert_eq!(text.parse(), Ok(ActivationKind::Relu))
+        };
+        for text in ["leaky_relu", "leaky-relu", "Leaky-ReLU", "leakyrelu"] {
+            assert_eq!(text.parse(), Ok(ActivationKind::LeakyRelu(LEAKY_ALPHA)))
+        };
+        for text in ["hard_sigmoid", "HARD-SIGMOID", "hardsigmoid"] {
+            assert_eq!(text.parse(), Ok(ActivationKind::HardSigmoid))
+        };
+        assert_eq!("linear".parse(), Ok(ActivationKind::Identity));
+        assert_eq!("SiLU".parse(), Ok(ActivationKind::Swish(1.0)))
+    }
+
+    #[test]
+    fn parse_parameters() {
+        assert_eq!("leaky_relu(0.2)".parse(), Ok(ActivationKind::LeakyRelu(0.2)));
+        assert_eq!("Leaky-ReLU( 0.3 )".parse(), Ok(ActivationKind::LeakyRelu(0.3)));
+        assert_eq!("elu(-1e-2)".parse(), Ok(ActivationKind::Elu(-0.01)));
+        assert_eq!("elu".parse(), Ok(ActivationKind::Elu(1.0)));
+        assert_eq!("swish(2)".parse(), Ok(ActivationKind::Swish(2.0)));
+        assert_eq!("swish".parse(), Ok(ActivationKind::Swish(1.0)))
+    }
+
+    #[test]
+    fn parse_errors() {
+        for text in ["", "relux", "soft_max", "leaky", "relu(0.5)", "silu(2)", "softmax()"] {
+            assert_eq!(text.parse::<ActivationKind>(), Err(ActivationParseError(text.to_string())), "{:?}", text)
+        };
+        for text in ["leaky_relu(abc)", "leaky_relu(0.2", "elu()", "swish(1, 2)", "elu(0.5))"] {
+            assert_eq!(text.parse::<ActivationKind>(), Err(ActivationParseError(text.to_string())), "{:?}", text)
+        };
+        assert_eq!("relux".parse::<ActivationKind>().unwrap_err().to_string(), "unknown activation `relux`")
+    }
+
+    #[test]
+    fn display_parses_back() {
+        for kind in KINDS {
+            assert_eq!(kind.to_string().parse(), Ok(kind), "{}", kind)
+        };
+        assert_eq!(ActivationKind::LeakyRelu(0.2).to_string(), "leaky_relu(0.2)")
+    }
+}
+
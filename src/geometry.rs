@@ -0,0 +1,240 @@
+use crate::lin_alg::{DeterNum, Determinant, Vector, VectorError};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Orientation {
+    CounterClockwise,
+    Clockwise,
+    Degenerate
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PointLocation {
+    Inside,
+    Outside,
+    Boundary
+}
+
+fn xy(point: &Vector) -> (f64, f64) {
+    if point.len() != 2 { panic!("Points must be 2-d!") }
+    (point.get_val()[0], point.get_val()[1])
+}
+
+// | ax bx |
+// | ay by |
+fn cross(a: (f64, f64), b: (f64, f64)) -> f64 {
+    match Determinant::from([[a.0, b.0], [a.1, b.1]]).cal() {
+        DeterNum::Float(num) => num,
+        _ => panic!("Shouldn't be here!")
+    }
+}
+
+// positive when c lies to the left of the line from a to b
+fn turn(a: (f64, f64), b: (f64, f64), c: (f64, f64)) -> f64 {
+    cross((b.0 - a.0, b.1 - a.1), (c.0 - a.0, c.1 - a.1))
+}
+
+// shoelace formula, positive for counter-clockwise vertices
+pub fn signed_area(polygon: &[Vector]) -> f64 {
+    let points = polygon.iter().map(xy).collect::<Vec<_>>();
+    let mut sum = 0.0;
+    for i in 0..points.len() {
+        sum += cross(points[i], points[(i + 1) % points.len()])
+    };
+    sum / 2.0
+}
+
+pub fn area(polygon: &[Vector]) -> f64 {
+    signed_area(polygon).abs()
+}
+
+pub fn orientation(polygon: &[Vector]) -> Orientation {
+    let area = signed_area(polygon);
+    if area > 0.0 {
+        Orientation::CounterClockwise
+    } else if area < 0.0 {
+        Orientation::Clockwise
+    } else {
+        Orientation::Degenerate
+    }
+}
+
+// area centroid of a simple polygon, the mean of the vertices when it has no area
+pub fn centroid(polygon: &[Vector]) -> Vector {
+    let points = polygon.iter().map(xy).collect::<Vec<_>>();
+    if points.is_empty() { panic!("The polygon has no vertex!") }
+    let area = signed_area(polygon);
+    if area == 0.0 {
+        let n = points.len() as f64;
+        return Vector::from([
+            points.iter().map(|i| i.0).sum::<f64>() / n,
+            points.iter().map(|i| i.1).sum::<f64>() / n
+        ])
+    }
+
+    let (mut x, mut y) = (0.0, 0.0);
+    for i in 0..points.len() {
+        let (a, b) = (points[i], points[(i + 1) % points.len()]);
+        let c = cross(a, b);
+        x += (a.0 + b.0) * c;
+        y += (a.1 + b.1) * c
+    };
+    Vector::from([x / (6.0 * area), y / (6.0 * area)])
+}
+
+// k-dimensional volume of the simplex on k + 1 vertices in n >= k dimensions
+pub fn simplex_volume(vertices: &[Vector]) -> Result<f64, VectorError> {
+    let (origin, rest) = match vertices.split_first() {
+        Some(split) => split,
+        None => return Err(VectorError::Arity { expected: 1, found: 0 })
+    };
+    if rest.len() > origin.len() {
+        return Err(VectorError::Arity { expected: origin.len() + 1, found: vertices.len() })
+    }
+    for vertex in rest.iter() {
+        if vertex.len() != origin.len() {
+            return Err(VectorError::Shape { expected: origin.len(), found: vertex.len() })
+        }
+    };
+    let edges = rest.iter().map(|i| i.clone() - origin.clone()).collect::<Vec<_>>();
+    let factorial = (1..=edges.len()).product::<usize>() as f64;
+    Ok(Vector::volume(&edges.iter().collect::<Vec<_>>())? / factorial)
+}
+
+// Andrew's monotone chain, counter-clockwise without collinear points
+pub fn convex_hull(points: &[Vector]) -> Vec<Vector> {
+    let mut sorted = points.iter().map(xy).collect::<Vec<_>>();
+    sorted.sort_by(|a, b| a.partial_cmp(b).expect("Points must not be NaN!"));
+    sorted.dedup();
+    if sorted.len() < 3 {
+        return sorted.into_iter().map(|i| Vector::from([i.0, i.1])).collect()
+    }
+
+    let mut hull: Vec<(f64, f64)> = Vec::new();
+    for pass in [sorted.clone(), sorted.into_iter().rev().collect()] {
+        let start = hull.len();
+        for point in pass {
+            while hull.len() >= start + 2 && turn(hull[hull.len() - 2], hull[hull.len() - 1], point) <= 0.0 {
+                hull.pop();
+            }
+            hull.push(point)
+        };
+        // the last point of each chain starts the next one
+        hull.pop();
+    };
+    hull.into_iter().map(|i| Vector::from([i.0, i.1])).collect()
+}
+
+// even-odd rule, points on an edge count as Boundary
+pub fn point_in_polygon(point: &Vector, polygon: &[Vector]) -> PointLocation {
+    let p = xy(point);
+    let points = polygon.iter().map(xy).collect::<Vec<_>>();
+    let mut inside = false;
+    for i in 0..points.len() {
+        let (a, b) = (points[i], points[(i + 1) % points.len()]);
+        if turn(a, b, p) == 0.0
+            && p.0 >= a.0.min(b.0) && p.0 <= a.0.max(b.0)
+            && p.1 >= a.1.min(b.1) && p.1 <= a.1.max(b.1) {
+            return PointLocation::Boundary
+        }
+        if (a.1 > p.1) != (b.1 > p.1) {
+            let x = a.0 + (p.1 - a.1) * (b.0 - a.0) / (b.1 - a.1);
+            if p.0 < x {
+                inside = !inside
+            }
+        }
+    };
+    if inside { PointLocation::Inside } else { PointLocation::Outside }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn points(points: &[[f64; 2]]) -> Vec<Vector> {
+        points.iter().map(|i| Vector::from(*i)).collect()
+    }
+
+    // an L shape, concave at (1, 1)
+    fn ell() -> Vec<Vector> {
+        points(&[[0.0, 0.0], [2.0, 0.0], [2.0, 1.0], [1.0, 1.0], [1.0, 2.0], [0.0, 2.0]])
+    }
+
+    #[test]
+    fn area_and_orientation() {
+        let square = points(&[[0.0, 0.0], [2.0, 0.0], [2.0, 2.0], [0.0, 2.0]]);
+        let reversed = square.iter().rev().cloned().collect::<Vec<_>>();
+        assert_eq!(signed_area(&square), 4.0);
+        assert_eq!(signed_area(&reversed), -4.0);
+        assert_eq!(area(&reversed), 4.0);
+        assert_eq!(orientation(&square), Orientation::CounterClockwise);
+        assert_eq!(orientation(&reversed), Orientation::Clockwise);
+        assert_eq!(orientation(&points(&[[0.0, 0.0], [1.0, 1.0], [2.0, 2.0]])), Orientation::Degenerate);
+        assert_eq!(area(&ell()), 3.0)
+    }
+
+    #[test]
+    fn centroid_of_polygons() {
+        let triangle = points(&[[0.0, 0.0], [3.0, 0.0], [0.0, 3.0]]);
+        crate::assert_vector_close!(centroid(&triangle), Vector::from([1.0, 1.0]));
+        // the vertex order does not matter
+        let reversed = triangle.iter().rev().cloned().collect::<Vec<_>>();
+        crate::assert_vector_close!(centroid(&reversed), Vector::from([1.0, 1.0]));
+        // the unit squares at (0.5, 0.5), (1.5, 0.5) and (0.5, 1.5)
+        crate::assert_vector_close!(centroid(&ell()), Vector::from([5.0 / 6.0, 5.0 / 6.0]));
+        // no area, the mean of the vertices
+        crate::assert_vector_close!(centroid(&points(&[[0.0, 0.0], [1.0, 1.0], [5.0, 5.0]])), Vector::from([2.0, 2.0]))
+    }
+
+    #[test]
+    fn point_location() {
+        let polygon = ell();
+        for vertex in polygon.iter() {
+            assert_eq!(point_in_polygon(vertex, &polygon), PointLocation::Boundary)
+        };
+        for point in [[1.0, 0.0], [2.0, 0.5], [1.5, 1.0], [1.0, 1.5], [0.0, 1.0]] {
+            assert_eq!(point_in_polygon(&Vector::from(point), &polygon), PointLocation::Boundary)
+        };
+        for point in [[0.5, 0.5], [1.5, 0.5], [0.5, 1.5], [0.5, 1.0], [1.0, 0.5]] {
+            assert_eq!(point_in_polygon(&Vector::from(point), &polygon), PointLocation::Inside)
+        };
+        // the notch of the L is inside its convex hull but outside the polygon
+        for point in [[1.5, 1.5], [1.1, 1.9], [3.0, 0.5], [-1.0, 1.0], [0.5, -0.5], [3.0, 3.0]] {
+            assert_eq!(point_in_polygon(&Vector::from(point), &polygon), PointLocation::Outside)
+        }
+    }
+
+    #[test]
+    fn convex_hull_drops_collinear_and_interior_points() {
+        let cloud = points(&[
+            [0.0, 0.0], [1.0, 0.0], [2.0, 0.0], [2.0, 1.0], [2.0, 2.0], [1.0, 2.0],
+            [0.0, 2.0], [0.0, 1.0], [1.0, 1.0], [0.5, 1.5], [2.0, 2.0]
+        ]);
+        let hull = convex_hull(&cloud);
+        let expected = points(&[[0.0, 0.0], [2.0, 0.0], [2.0, 2.0], [0.0, 2.0]]);
+        assert_eq!(hull.len(), expected.len());
+        for (a, b) in hull.iter().zip(expected.iter()) {
+            crate::assert_vector_close!(a, b)
+        };
+        // all on one line, only the two ends are left
+        let hull = convex_hull(&points(&[[1.0, 1.0], [0.0, 0.0], [3.0, 3.0], [2.0, 2.0]]));
+        assert_eq!(hull.len(), 2);
+        crate::assert_vector_close!(hull[0], Vector::from([0.0, 0.0]));
+        crate::assert_vector_close!(hull[1], Vector::from([3.0, 3.0]))
+    }
+
+    #[test]
+    fn simplex_volumes() {
+        let triangle = points(&[[0.0, 0.0], [1.0, 0.0], [0.0, 1.0]]);
+        assert!((simplex_volume(&triangle).unwrap() - 0.5).abs() < 1e-12);
+        let tetrahedron = [
+            Vector::from([0.0, 0.0, 0.0]), Vector::from([1.0, 0.0, 0.0]),
+            Vector::from([0.0, 1.0, 0.0]), Vector::from([0.0, 0.0, 1.0])
+        ];
+        assert!((simplex_volume(&tetrahedron).unwrap() - 1.0 / 6.0).abs() < 1e-12);
+        // a triangle standing in 3-d space
+        assert!((simplex_volume(&tetrahedron[1..]).unwrap() - 3f64.sqrt() / 2.0).abs() < 1e-12);
+        assert_eq!(simplex_volume(&[]), Err(VectorError::Arity { expected: 1, found: 0 }));
+        assert_eq!(simplex_volume(&points(&[[0.0, 0.0], [1.0, 0.0], [0.0, 1.0], [1.0, 1.0]])), Err(VectorError::Arity { expected: 3, found: 4 }));
+        assert_eq!(simplex_volume(&[Vector::from([0.0, 0.0]), Vector::from([1.0])]), Err(VectorError::Shape { expected: 2, found: 1 }))
+    }
+}
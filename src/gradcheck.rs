@@ -0,0 +1,123 @@
+use crate::lin_alg::{Matrix, DEFAULT_ATOL, DEFAULT_RTOL};
+
+// small enough for the O(eps^2) truncation error, large enough to keep rounding noise down
+pub const GRADCHECK_EPS: f64 = 1e-6;
+
+#[derive(Clone, Debug)]
+pub struct GradCheck {
+    pub analytic: Matrix,
+    pub numeric: Matrix,
+    pub max_abs_error: f64,
+    // |a - n| / max(|a|, |n|), 0 where both are 0
+    pub max_rel_error: f64,
+    // (x, y) of the largest absolute error, in the order of Matrix::get
+    pub worst: (usize, usize)
+}
+
+impl GradCheck {
+    // the numpy.isclose rule on every element
+    pub fn passed(&self, rtol: f64, atol: f64) -> bool {
+        self.analytic.approx_eq(&self.numeric, rtol, atol)
+    }
+
+    pub fn passed_default(&self) -> bool {
+        self.passed(DEFAULT_RTOL, DEFAULT_ATOL)
+    }
+}
+
+impl std::fmt::Display for GradCheck {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (x, y) = self.worst;
+        write!(
+            f, "max abs error: {:e}, max rel error: {:e}, worst at ({}, {}): analytic {}, numeric {}",
+            self.max_abs_error, self.max_rel_error, x, y, self.analytic.get(x, y), self.numeric.get(x, y)
+        )
+    }
+}
+
+pub fn gradcheck(f: impl Fn(&Matrix) -> f64, at: &Matrix, analytic: &Matrix) -> GradCheck {
+    gradcheck_with(f, at, analytic, GRADCHECK_EPS)
+}
+
+// central differences (f(x + eps) - f(x - eps)) / 2eps on one element at a time
+pub fn gradcheck_with(f: impl Fn(&Matrix) -> f64, at: &Matrix, analytic: &Matrix, eps: f64) -> GradCheck {
+    if !at.same_shape(analytic) { panic!("The shapes are not matched!") }
+    let (cols, rows) = at.shape();
+    let mut numeric = Matrix::zeros(at.shape());
+    let mut point = at.clone();
+    let (mut max_abs_error, mut max_rel_error, mut worst) = (0.0, 0.0, (0, 0));
+
+    for y in 0..rows {
+        for x in 0..cols {
+            let val = at.get(x, y);
+            point.change_place((x, y), val + eps);
+            let up = f(&point);
+            point.change_place((x, y), val - eps);
+            let down = f(&point);
+            point.change_place((x, y), val);
+
+            let num = (up - down) / (2.0 * eps);
+            numeric.change_place((x, y), num);
+            let a = analytic.get(x, y);
+            let abs_error = (a - num).abs();
+            let scale = a.abs().max(num.abs());
+            let rel_error = if scale == 0.0 { 0.0 } else { abs_error / scale };
+            if abs_error > max_abs_error {
+                (max_abs_error, worst) = (abs_error, (x, y))
+            }
+            if rel_error > max_rel_error {
+                max_rel_error = rel_error
+            }
+        }
+    };
+
+    GradCheck { analytic: analytic.clone(), numeric, max_abs_error, max_rel_error, worst }
+}
+
+// fixtures for the gradient tests across the crate
+#[cfg(test)]
+pub(crate) mod testing {
+    use crate::lin_alg::Matrix;
+
+    // fixed values spread over [-scale, scale], so the checks don't land on a kink by accident
+    pub fn sample(shape: (usize, usize), seed: usize, scale: f64) -> Matrix {
+        let mut out = Matrix::zeros(shape);
+        for y in 0..shape.1 {
+            for x in 0..shape.0 {
+                out.change_place((x, y), scale * ((x * 7 + y * 13 + seed * 31 + 1) as f64 * 0.737).sin())
+            }
+        };
+        out
+    }
+
+    // <a, b> summed over every element, turns a Matrix output into a scalar with gradient b
+    pub fn dot(a: &Matrix, b: &Matrix) -> f64 {
+        a.had_product(b.clone()).iter().map(|i| i.sum()).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::testing::{dot, sample};
+    use super::*;
+
+    #[test]
+    fn exact_gradient_passes() {
+        let at = sample((3, 2), 0, 2.0);
+        let check = gradcheck(|m| dot(m, m), &at, &at.strech(2.0));
+        assert!(check.passed_default(), "{}", check);
+        assert!(check.max_abs_error < 1e-8)
+    }
+
+    #[test]
+    fn reports_the_worst_element() {
+        let at = sample((3, 2), 1, 1.0);
+        let mut analytic = at.strech(2.0);
+        analytic.change_place((2, 1), analytic.get(2, 1) + 0.5);
+        let check = gradcheck(|m| dot(m, m), &at, &analytic);
+        assert!(!check.passed_default());
+        assert_eq!(check.worst, (2, 1));
+        assert!((check.max_abs_error - 0.5).abs() < 1e-6);
+        crate::assert_matrix_close!(check.numeric, at.strech(2.0))
+    }
+}
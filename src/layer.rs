@@ -0,0 +1,277 @@
+use rand::Rng;
+
+use crate::func::{Activation, ActivationKind};
+use crate::lin_alg::Matrix;
+
+// one step of a model, every batch holds one sample per column
+pub trait Layer {
+    fn forward(&mut self, input: &Matrix) -> Matrix;
+    // takes dL/d(output), adds to the parameter gradients and gives back dL/d(input)
+    fn backward(&mut self, grad: &Matrix) -> Matrix;
+    // the forward pass without caching anything for backward
+    fn predict(&self, input: &Matrix) -> Matrix;
+    fn parameters(&self) -> Vec<&Matrix>;
+    fn gradients(&self) -> Vec<&Matrix>;
+    // every parameter next to its accumulated gradient, for an optimizer to step
+    fn parameters_mut(&mut self) -> Vec<(&mut Matrix, &Matrix)>;
+    fn zero_grad(&mut self);
+    // the number of rows taken and given, None for layers that work on any number of rows
+    fn input_size(&self) -> Option<usize>;
+    fn output_size(&self) -> Option<usize>;
+    // layers that act differently while training, like dropout, switch on this
+    fn set_training(&mut self, _training: bool) {}
+    // layers without a mode always count as training
+    fn is_training(&self) -> bool {
+        true
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum Initializer {
+    Zeros,
+    Constant(f64),
+    // uniform on [-limit, limit]
+    Uniform(f64),
+    // normal with mean 0 and the given standard deviation
+    Normal(f64),
+    // Glorot & Bengio, uniform with limit sqrt(6 / (input + output)), good for tanh and sigmoid
+    #[default]
+    Xavier,
+    // He et al., normal with std sqrt(2 / input), good for the ReLU family
+    He
+}
+
+fn uniform(rng: &mut impl Rng, limit: f64) -> f64 {
+    limit * (2.0 * rng.gen::<f64>() - 1.0)
+}
+
+// Box-Muller, 1 - u keeps ln() away from 0
+fn normal(rng: &mut impl Rng, std: f64) -> f64 {
+    let (u, v) = (rng.gen::<f64>(), rng.gen::<f64>());
+    std * (-2.0 * (1.0 - u).ln()).sqrt() * (2.0 * std::f64::consts::PI * v).cos()
+}
+
+impl Initializer {
+    // an `output` x `input` matrix, so it maps `input` rows to `output` rows
+    pub fn matrix(&self, input: usize, output: usize, rng: &mut impl Rng) -> Matrix {
+        let mut out = Matrix::zeros((input, output));
+        for y in 0..output {
+            for x in 0..input {
+                let val = match *self {
+                    Initializer::Zeros => 0.0,
+                    Initializer::Constant(val) => val,
+                    Initializer::Uniform(limit) => uniform(rng, limit),
+                    Initializer::Normal(std) => normal(rng, std),
+                    Initializer::Xavier => uniform(rng, (6.0 / (input + output) as f64).sqrt()),
+                    Initializer::He => normal(rng, (2.0 / input as f64).sqrt())
+                };
+                out.change_place((x, y), val)
+            }
+        };
+        out
+    }
+}
+
+// y = f(W x + b) on a batch with one sample per column, the layout softmax and the losses use
+#[derive(Clone, Debug)]
+pub struct Dense {
+    // output x input
+    weights: Matrix,
+    // output x 1, added to every column
+    bias: Matrix,
+    activation: ActivationKind,
+    weights_grad: Matrix,
+    bias_grad: Matrix,
+    // the input and W x + b of the last forward, kept for backward
+    input: Option<Matrix>,
+    pre_activation: Option<Matrix>
+}
+
+impl Dense {
+    // Xavier weights, zero bias and no activation
+    pub fn new(input: usize, output: usize) -> Self {
+        if input == 0 || output == 0 { panic!("The layer can't be empty!") }
+        Self::from_parts(
+            Initializer::default().matrix(input, output, &mut rand::thread_rng()),
+            Matrix::zeros((1, output)),
+            ActivationKind::Identity
+        )
+    }
+
+    pub fn from_parts(weights: Matrix, bias: Matrix, activation: ActivationKind) -> Self {
+        if bias.shape() != (1, weights.shape().1) { panic!("The bias must be a column with a row per output!") }
+        Self {
+            weights_grad: Matrix::zeros(weights.shape()),
+            bias_grad: Matrix::zeros(bias.shape()),
+            weights,
+            bias,
+            activation,
+            input: None,
+            pre_activation: None
+        }
+    }
+
+    pub fn activation(mut self, activation: ActivationKind) -> Self {
+        self.activation = activation;
+        self
+    }
+
+    // redraws the weights from a fresh thread_rng
+    pub fn initializer(self, initializer: Initializer) -> Self {
+        self.init_with(initializer, &mut rand::thread_rng())
+    }
+
+    // redraws the weights from the given rng, e.g. a seeded StdRng for reproducible runs
+    pub fn init_with(mut self, initializer: Initializer, rng: &mut impl Rng) -> Self {
+        let (input, output) = self.weights.shape();
+        self.weights = initializer.matrix(input, output, rng);
+        self
+    }
+
+    pub fn weights(&self) -> &Matrix {
+        &self.weights
+    }
+
+    pub fn bias(&self) -> &Matrix {
+        &self.bias
+    }
+
+    pub fn weights_grad(&self) -> &Matrix {
+        &self.weights_grad
+    }
+
+    pub fn bias_grad(&self) -> &Matrix {
+        &self.bias_grad
+    }
+
+    pub fn activation_kind(&self) -> ActivationKind {
+        self.activation
+    }
+
+    fn affine(&self, input: &Matrix) -> Matrix {
+        if input.shape().1 != self.weights.shape().0 { panic!("The input must have a row per input of the layer!") }
+        let mut out = self.weights.clone() * input.clone();
+        for y in 0..out.shape().1 {
+            let bias = self.bias.get(0, y);
+            for x in 0..out.shape().0 {
+                out.change_place((x, y), out.get(x, y) + bias)
+            }
+        };
+        out
+    }
+}
+
+impl Layer for Dense {
+    fn forward(&mut self, input: &Matrix) -> Matrix {
+        let pre_activation = self.affine(input);
+        let out = pre_activation.forward(self.activation);
+        self.input = Some(input.clone());
+        self.pre_activation = Some(pre_activation);
+        out
+    }
+
+    fn backward(&mut self, grad: &Matrix) -> Matrix {
+        let (input, pre_activation) = match (&self.input, &self.pre_activation) {
+            (Some(input), Some(pre_activation)) => (input, pre_activation),
+            _ => panic!("Backward needs a forward first!")
+        };
+        if !grad.same_shape(pre_activation) { panic!("The gradient must have the shape of the output!") }
+        let delta = pre_activation.backward(self.activation, grad);
+
+        self.weights_grad += delta.clone() * input.t();
+        let row_sum = delta.sum().0;
+        self.bias_grad += Matrix::from_vec(row_sum.into_iter().map(|i| vec![i]).collect());
+        self.weights.t() * delta
+    }
+
+    fn predict(&self, input: &Matrix) -> Matrix {
+        self.affine(input).forward(self.activation)
+    }
+
+    fn parameters(&self) -> Vec<&Matrix> {
+        vec![&self.weights, &self.bias]
+    }
+
+    fn gradients(&self) -> Vec<&Matrix> {
+        vec![&self.weights_grad, &self.bias_grad]
+    }
+
+    fn parameters_mut(&mut self) -> Vec<(&mut Matrix, &Matrix)> {
+        vec![(&mut self.weights, &self.weights_grad), (&mut self.bias, &self.bias_grad)]
+    }
+
+    fn zero_grad(&mut self) {
+        self.weights_grad = Matrix::zeros(self.weights.shape());
+        self.bias_grad = Matrix::zeros(self.bias.shape())
+    }
+
+    fn input_size(&self) -> Option<usize> {
+        Some(self.weights.shape().0)
+    }
+
+    fn output_size(&self) -> Option<usize> {
+        Some(self.weights.shape().1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gradcheck::gradcheck;
+    use crate::gradcheck::testing::{dot, sample};
+
+    #[test]
+    fn backward_matches_gradcheck() {
+        let (input, output, batch) = (4, 3, 5);
+        let x = sample((batch, input), 8, 1.0);
+        let grad = sample((batch, output), 9, 1.0);
+        let weights = sample((input, output), 10, 0.8);
+        let bias = sample((1, output), 11, 0.3);
+        let kinds = [
+            ActivationKind::Identity, ActivationKind::Sigmoid, ActivationKind::Tanh, ActivationKind::Gelu,
+            ActivationKind::Swish(1.0), ActivationKind::Mish, ActivationKind::Softmax
+        ];
+        for kind in kinds {
+            let mut layer = Dense::from_parts(weights.clone(), bias.clone(), kind);
+            layer.forward(&x);
+            let input_grad = layer.backward(&grad);
+
+            let check = gradcheck(|w| dot(&Dense::from_parts(w.clone(), bias.clone(), kind).predict(&x), &grad), &weights, layer.weights_grad());
+            assert!(check.passed(1e-5, 1e-7), "{} weights: {}", kind, check);
+            let check = gradcheck(|b| dot(&Dense::from_parts(weights.clone(), b.clone(), kind).predict(&x), &grad), &bias, layer.bias_grad());
+            assert!(check.passed(1e-5, 1e-7), "{} bias: {}", kind, check);
+            let check = gradcheck(|i| dot(&layer.predict(i), &grad), &x, &input_grad);
+            assert!(check.passed(1e-5, 1e-7), "{} input: {}", kind, check)
+        }
+    }
+
+    #[test]
+    fn gradients_accumulate_until_zeroed() {
+        let x = sample((3, 2), 1, 1.0);
+        let grad = sample((3, 2), 2, 1.0);
+        let mut layer = Dense::from_parts(sample((2, 2), 3, 1.0), Matrix::zeros((1, 2)), ActivationKind::Tanh);
+        layer.forward(&x);
+        layer.backward(&grad);
+        let (weights_grad, bias_grad) = (layer.weights_grad().clone(), layer.bias_grad().clone());
+        layer.backward(&grad);
+        crate::assert_matrix_close!(layer.weights_grad(), weights_grad.strech(2.0));
+        crate::assert_matrix_close!(layer.bias_grad(), bias_grad.strech(2.0));
+        layer.zero_grad();
+        crate::assert_matrix_close!(layer.weights_grad(), Matrix::zeros((2, 2)));
+        crate::assert_matrix_close!(layer.bias_grad(), Matrix::zeros((1, 2)))
+    }
+
+    #[test]
+    fn forward_adds_the_bias_to_every_column() {
+        let layer = Dense::from_parts(Matrix::from([[1.0, 2.0], [0.0, -1.0]]), Matrix::from([[0.5], [1.0]]), ActivationKind::Identity);
+        let out = layer.predict(&Matrix::from([[1.0, 0.0, 2.0], [1.0, 1.0, -1.0]]));
+        crate::assert_matrix_close!(out, Matrix::from([[3.5, 2.5, 0.5], [0.0, 0.0, 2.0]]));
+        assert_eq!((layer.input_size(), layer.output_size()), (Some(2), Some(2)))
+    }
+
+    #[test]
+    #[should_panic(expected = "Backward needs a forward first!")]
+    fn backward_without_forward() {
+        Dense::new(2, 3).backward(&Matrix::zeros((1, 3)));
+    }
+}
@@ -0,0 +1,12 @@
+pub mod lin_alg;
+pub mod func;
+pub mod data_pro;
+pub mod geometry;
+pub mod loss;
+pub mod autograd;
+pub mod dual;
+pub mod gradcheck;
+pub mod layer;
+pub mod model;
+pub mod optim;
+pub mod trainer;
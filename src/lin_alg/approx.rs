@@ -0,0 +1,48 @@
+pub const DEFAULT_RTOL: f64 = 1e-5;
+pub const DEFAULT_ATOL: f64 = 1e-8;
+
+// the same rule as numpy.isclose: |a - b| <= atol + rtol * |b|
+pub fn close(a: f64, b: f64, rtol: f64, atol: f64) -> bool {
+    if a == b {
+        return true
+    }
+    (a - b).abs() <= atol + rtol * b.abs()
+}
+
+#[macro_export]
+macro_rules! assert_vector_close {
+    ($left:expr, $right:expr) => {
+        $crate::assert_vector_close!($left, $right, $crate::lin_alg::DEFAULT_RTOL, $crate::lin_alg::DEFAULT_ATOL)
+    };
+    ($left:expr, $right:expr, $rtol:expr, $atol:expr) => {{
+        let (left, right) = (&$left, &$right);
+        if left.shape() != right.shape() {
+            panic!("assertion failed: vectors have different shapes, left: {}, right: {}", left.shape(), right.shape())
+        }
+        if let Some((index, a, b)) = left.first_mismatch(right, $rtol, $atol) {
+            panic!(
+                "assertion failed: vectors are not close at index {}, left: {}, right: {} (diff: {}, rtol: {}, atol: {})",
+                index, a, b, (a - b).abs(), $rtol, $atol
+            )
+        }
+    }};
+}
+
+#[macro_export]
+macro_rules! assert_matrix_close {
+    ($left:expr, $right:expr) => {
+        $crate::assert_matrix_close!($left, $right, $crate::lin_alg::DEFAULT_RTOL, $crate::lin_alg::DEFAULT_ATOL)
+    };
+    ($left:expr, $right:expr, $rtol:expr, $atol:expr) => {{
+        let (left, right) = (&$left, &$right);
+        if left.shape() != right.shape() {
+            panic!("assertion failed: matrices have different shapes, left: {:?}, right: {:?}", left.shape(), right.shape())
+        }
+        if let Some(((x, y), a, b)) = left.first_mismatch(right, $rtol, $atol) {
+            panic!(
+                "assertion failed: matrices are not close at ({}, {}), left: {}, right: {} (diff: {}, rtol: {}, atol: {})",
+                x, y, a, b, (a - b).abs(), $rtol, $atol
+            )
+        }
+    }};
+}
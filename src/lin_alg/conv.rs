@@ -0,0 +1,288 @@
+use super::Matrix;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Padding {
+    Valid,
+    // the output keeps ceil(input / stride) rows and columns, extra padding goes to the bottom / right
+    Same,
+    // (cols, rows): zero columns added left and right, zero rows added above and below
+    Explicit(usize, usize)
+}
+
+// every pair is (cols, rows), the order of Matrix::shape
+#[derive(Clone, Copy, Debug)]
+pub struct ConvOptions {
+    pub stride: (usize, usize),
+    pub padding: Padding,
+    pub dilation: (usize, usize)
+}
+
+impl Default for ConvOptions {
+    fn default() -> Self {
+        Self { stride: (1, 1), padding: Padding::Valid, dilation: (1, 1) }
+    }
+}
+
+impl ConvOptions {
+    pub fn stride(mut self, cols: usize, rows: usize) -> Self {
+        self.stride = (cols, rows);
+        self
+    }
+
+    pub fn padding(mut self, padding: Padding) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    pub fn dilation(mut self, cols: usize, rows: usize) -> Self {
+        self.dilation = (cols, rows);
+        self
+    }
+}
+
+// sizes along one axis: (padding before, output length)
+fn axis(input: usize, kernel: usize, stride: usize, dilation: usize, padding: Padding, explicit: usize) -> (usize, usize) {
+    if stride == 0 || dilation == 0 { panic!("Stride and dilation must be positive!") }
+    let effective = (kernel - 1) * dilation + 1;
+    let (before, total) = match padding {
+        Padding::Valid => (0, 0),
+        Padding::Explicit(..) => (explicit, explicit * 2),
+        Padding::Same => {
+            let out = input.div_ceil(stride);
+            let total = ((out - 1) * stride + effective).saturating_sub(input);
+            (total / 2, total)
+        }
+    };
+    if input + total < effective { panic!("The kernel is larger than the padded input!") }
+    (before, (input + total - effective) / stride + 1)
+}
+
+// everything in here is (rows, cols), the order the loops run in
+struct Geometry {
+    channels: usize,
+    input: (usize, usize),
+    kernel: (usize, usize),
+    pad: (usize, usize),
+    output: (usize, usize),
+    stride: (usize, usize),
+    dilation: (usize, usize)
+}
+
+impl Geometry {
+    // input and kernel shapes come in as Matrix::shape gives them, (cols, rows)
+    fn new(channels: usize, input: (usize, usize), kernel: (usize, usize), options: &ConvOptions) -> Self {
+        let swap = |(x, y): (usize, usize)| (y, x);
+        let (input, kernel, stride, dilation) = (swap(input), swap(kernel), swap(options.stride), swap(options.dilation));
+        let explicit = match options.padding {
+            Padding::Explicit(cols, rows) => (rows, cols),
+            _ => (0, 0)
+        };
+        let (pad_r, out_r) = axis(input.0, kernel.0, stride.0, dilation.0, options.padding, explicit.0);
+        let (pad_c, out_c) = axis(input.1, kernel.1, stride.1, dilation.1, options.padding, explicit.1);
+        Self {
+            channels, input, kernel,
+            pad: (pad_r, pad_c),
+            output: (out_r, out_c),
+            stride,
+            dilation
+        }
+    }
+
+    // the input position read by kernel tap (kr, kc) at output position (or, oc), None inside the padding
+    fn source(&self, or: usize, oc: usize, kr: usize, kc: usize) -> Option<(usize, usize)> {
+        let r = (or * self.stride.0 + kr * self.dilation.0).checked_sub(self.pad.0)?;
+        let c = (oc * self.stride.1 + kc * self.dilation.1).checked_sub(self.pad.1)?;
+        if r < self.input.0 && c < self.input.1 { Some((r, c)) } else { None }
+    }
+
+    fn patch_len(&self) -> usize {
+        self.channels * self.kernel.0 * self.kernel.1
+    }
+
+    // one row per (channel, kr, kc), one column per output position
+    fn im2col(&self, input: &[Matrix]) -> Matrix {
+        let mut buf = Vec::with_capacity(self.patch_len());
+        for channel in input.iter() {
+            for kr in 0..self.kernel.0 {
+                for kc in 0..self.kernel.1 {
+                    let mut row = Vec::new();
+                    for or in 0..self.output.0 {
+                        for oc in 0..self.output.1 {
+                            row.push(match self.source(or, oc, kr, kc) {
+                                Some((r, c)) => channel.get(c, r),
+                                None => 0.0
+                            })
+                        }
+                    };
+                    buf.push(row)
+                }
+            }
+        };
+        Matrix::from_vec(buf)
+    }
+
+    fn col2im(&self, cols: &Matrix) -> Vec<Matrix> {
+        let mut out = vec![vec![vec![0.0; self.input.1]; self.input.0]; self.channels];
+        for (index, row) in cols.iter().enumerate() {
+            let channel = index / (self.kernel.0 * self.kernel.1);
+            let kr = index / self.kernel.1 % self.kernel.0;
+            let kc = index % self.kernel.1;
+            for (place, val) in row.iter().enumerate() {
+                if let Some((r, c)) = self.source(place / self.output.1, place % self.output.1, kr, kc) {
+                    out[channel][r][c] += val
+                }
+            }
+        };
+        out.into_iter().map(Matrix::from_vec).collect()
+    }
+
+    fn kernel_matrix(&self, kernel: &[Vec<Matrix>]) -> Matrix {
+        let mut buf = Vec::new();
+        for out_channel in kernel.iter() {
+            if out_channel.len() != self.channels { panic!("The kernel channels do not match the input!") }
+            let mut row = Vec::new();
+            for k in out_channel.iter() {
+                if k.shape() != (self.kernel.1, self.kernel.0) { panic!("The kernels have different shapes!") }
+                for i in k.iter() {
+                    row.extend_from_slice(i.get_val())
+                }
+            };
+            buf.push(row)
+        };
+        Matrix::from_vec(buf)
+    }
+
+    // split every row of an (out channels x positions) matrix back into an output map
+    fn unflatten(&self, flat: &Matrix) -> Vec<Matrix> {
+        flat.iter().map(|row| {
+            Matrix::from_vec(row.get_val().chunks(self.output.1).map(|i| i.to_vec()).collect())
+        }).collect()
+    }
+
+    fn flatten(&self, maps: &[Matrix]) -> Matrix {
+        Matrix::from_vec(maps.iter().map(|map| {
+            if map.shape() != (self.output.1, self.output.0) { panic!("The gradient does not match the output shape!") }
+            map.iter().flat_map(|i| i.iter().copied()).collect()
+        }).collect())
+    }
+}
+
+fn input_size(input: &[Matrix]) -> (usize, usize) {
+    let first = input.first().expect("The input has no channel!");
+    if input.iter().any(|i| !i.same_shape(first)) { panic!("The input channels have different shapes!") }
+    first.shape()
+}
+
+fn kernel_size(kernel: &[Vec<Matrix>]) -> (usize, usize) {
+    let first = kernel.first().and_then(|i| i.first()).expect("The kernel is empty!");
+    first.shape()
+}
+
+fn rot180(matrix: &Matrix) -> Matrix {
+    Matrix::from_vec(matrix.iter().rev().map(|i| i.iter().rev().copied().collect()).collect())
+}
+
+fn flip_kernel(kernel: &[Vec<Matrix>]) -> Vec<Vec<Matrix>> {
+    kernel.iter().map(|i| i.iter().map(rot180).collect()).collect()
+}
+
+// cross-correlation, input is [in channel], kernel is [out channel][in channel], output is [out channel]
+pub fn correlate2d(input: &[Matrix], kernel: &[Vec<Matrix>], options: &ConvOptions) -> Vec<Matrix> {
+    let geo = Geometry::new(input.len(), input_size(input), kernel_size(kernel), options);
+    geo.unflatten(&(geo.kernel_matrix(kernel) * geo.im2col(input)))
+}
+
+// gradient of correlate2d with respect to its input, given the gradient of the output,
+// `input_shape` is the shape of one input channel
+pub fn correlate2d_grad_input(grad: &[Matrix], kernel: &[Vec<Matrix>], channels: usize, input_shape: (usize, usize), options: &ConvOptions) -> Vec<Matrix> {
+    let geo = Geometry::new(channels, input_shape, kernel_size(kernel), options);
+    geo.col2im(&(geo.kernel_matrix(kernel).t() * geo.flatten(grad)))
+}
+
+// gradient of correlate2d with respect to its kernel, given the gradient of the output,
+// `kernel_shape` is the shape of one kernel
+pub fn correlate2d_grad_kernel(grad: &[Matrix], input: &[Matrix], kernel_shape: (usize, usize), options: &ConvOptions) -> Vec<Vec<Matrix>> {
+    let geo = Geometry::new(input.len(), input_size(input), kernel_shape, options);
+    let flat = geo.flatten(grad) * geo.im2col(input).t();
+    flat.iter().map(|row| {
+        row.get_val().chunks(geo.kernel.0 * geo.kernel.1).map(|k| {
+            Matrix::from_vec(k.chunks(geo.kernel.1).map(|i| i.to_vec()).collect())
+        }).collect()
+    }).collect()
+}
+
+// true convolution, the kernel is rotated by 180 degrees before it slides over the input
+pub fn conv2d(input: &[Matrix], kernel: &[Vec<Matrix>], options: &ConvOptions) -> Vec<Matrix> {
+    correlate2d(input, &flip_kernel(kernel), options)
+}
+
+pub fn conv2d_grad_input(grad: &[Matrix], kernel: &[Vec<Matrix>], channels: usize, input_shape: (usize, usize), options: &ConvOptions) -> Vec<Matrix> {
+    correlate2d_grad_input(grad, &flip_kernel(kernel), channels, input_shape, options)
+}
+
+pub fn conv2d_grad_kernel(grad: &[Matrix], input: &[Matrix], kernel_shape: (usize, usize), options: &ConvOptions) -> Vec<Vec<Matrix>> {
+    flip_kernel(&correlate2d_grad_kernel(grad, input, kernel_shape, options))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gradcheck::gradcheck;
+    use crate::gradcheck::testing::{dot, sample};
+
+    #[test]
+    fn correlate_known_values() {
+        // 3 columns x 2 rows of kernel over a 4 x 3 input
+        let input = Matrix::from([[1.0, 2.0, 3.0, 4.0], [5.0, 6.0, 7.0, 8.0], [9.0, 10.0, 11.0, 12.0]]);
+        let kernel = Matrix::from([[1.0, 2.0, 0.0], [0.0, 0.0, -1.0]]);
+        let (input, kernel) = ([input], [vec![kernel]]);
+        let out = correlate2d(&input, &kernel, &ConvOptions::default());
+        crate::assert_matrix_close!(out[0], Matrix::from([[-2.0, 0.0], [6.0, 8.0]]));
+        // the true convolution flips the kernel
+        let out = conv2d(&input, &kernel, &ConvOptions::default());
+        crate::assert_matrix_close!(out[0], Matrix::from([[18.0, 20.0], [26.0, 28.0]]))
+    }
+
+    #[test]
+    fn options_are_cols_then_rows() {
+        let input = [sample((7, 4), 0, 1.0)];
+        let kernel = [vec![sample((3, 1), 1, 1.0)]];
+        let out = correlate2d(&input, &kernel, &ConvOptions::default().stride(2, 1));
+        assert_eq!(out[0].shape(), (3, 4));
+        let out = correlate2d(&input, &kernel, &ConvOptions::default().padding(Padding::Explicit(1, 0)));
+        assert_eq!(out[0].shape(), (7, 4));
+        let out = correlate2d(&input, &kernel, &ConvOptions::default().dilation(3, 1));
+        assert_eq!(out[0].shape(), (1, 4))
+    }
+
+    #[test]
+    fn gradients() {
+        let options = [
+            ConvOptions::default(),
+            ConvOptions::default().stride(1, 2).padding(Padding::Same),
+            ConvOptions::default().padding(Padding::Explicit(2, 1)).dilation(2, 1)
+        ];
+        let input = vec![sample((6, 5), 17, 1.0), sample((6, 5), 18, 1.0)];
+        // non-square, so a mixed-up (cols, rows) order can't pass
+        let kernel = vec![vec![sample((2, 3), 19, 1.0), sample((2, 3), 20, 1.0)]];
+        let (input_shape, kernel_shape) = (input[0].shape(), kernel[0][0].shape());
+        for option in options.iter() {
+            for flipped in [false, true] {
+                let run = |input: &[Matrix], kernel: &[Vec<Matrix>]| {
+                    if flipped { conv2d(input, kernel, option) } else { correlate2d(input, kernel, option) }
+                };
+                let grad = vec![sample(run(&input, &kernel)[0].shape(), 21, 1.0)];
+                let (input_grad, kernel_grad) = if flipped {
+                    (conv2d_grad_input(&grad, &kernel, 2, input_shape, option), conv2d_grad_kernel(&grad, &input, kernel_shape, option))
+                } else {
+                    (correlate2d_grad_input(&grad, &kernel, 2, input_shape, option), correlate2d_grad_kernel(&grad, &input, kernel_shape, option))
+                };
+
+                let check = gradcheck(|i| dot(&run(&[i.clone(), input[1].clone()], &kernel)[0], &grad[0]), &input[0], &input_grad[0]);
+                assert!(check.passed(1e-5, 1e-7), "{:?} flipped {} input: {}", option, flipped, check);
+                let check = gradcheck(|k| dot(&run(&input, &[vec![kernel[0][0].clone(), k.clone()]])[0], &grad[0]), &kernel[0][1], &kernel_grad[0][1]);
+                assert!(check.passed(1e-5, 1e-7), "{:?} flipped {} kernel: {}", option, flipped, check)
+            }
+        }
+    }
+}
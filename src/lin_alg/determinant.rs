@@ -1,29 +1,65 @@
 use std::iter::zip;
 
-use super::{Matrix, Vector};
+use super::{Fraction, Matrix, Vector, approx};
 
 #[derive(Clone, Debug)]
 pub enum DeterNum {
     Vec(Vector),
-    Float(f64)
+    Float(f64),
+    // exact entries, mixing them with floats gives a float
+    Frac(Fraction)
 }
 
 impl std::fmt::Display for DeterNum {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let words = match self {
             DeterNum::Vec(vec) => format!("{}", vec),
-            DeterNum::Float(float) => format!("{}", float)
+            DeterNum::Float(float) => format!("{}", float),
+            DeterNum::Frac(frac) => format!("{}", frac)
         };
         write!(f, "{}", words)
     }
 }
 
+impl DeterNum {
+    pub fn approx_eq(&self, rhs: &DeterNum, rtol: f64, atol: f64) -> bool {
+        match (self, rhs) {
+            (DeterNum::Float(a), DeterNum::Float(b)) => approx::close(*a, *b, rtol, atol),
+            (DeterNum::Vec(a), DeterNum::Vec(b)) => a.approx_eq(b, rtol, atol),
+            (DeterNum::Frac(a), DeterNum::Frac(b)) => a == b,
+            (DeterNum::Frac(a), DeterNum::Float(b)) => approx::close(a.to_f64(), *b, rtol, atol),
+            (DeterNum::Float(a), DeterNum::Frac(b)) => approx::close(*a, b.to_f64(), rtol, atol),
+            _ => false
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        match self {
+            DeterNum::Float(float) => *float == 0.0,
+            DeterNum::Frac(frac) => frac.is_zero(),
+            DeterNum::Vec(_) => false
+        }
+    }
+
+    // the number as a float, None for vectors
+    pub fn to_f64(&self) -> Option<f64> {
+        match self {
+            DeterNum::Float(float) => Some(*float),
+            DeterNum::Frac(frac) => Some(frac.to_f64()),
+            DeterNum::Vec(_) => None
+        }
+    }
+}
+
 impl std::ops::Add for DeterNum {
     type Output = Self;
     fn add(self, rhs: Self) -> Self::Output {
         match (self, rhs) {
             (DeterNum::Float(a), DeterNum::Float(b)) => DeterNum::Float(a+b),
             (DeterNum::Vec(a), DeterNum::Vec(b)) => DeterNum::Vec(a+b),
+            (DeterNum::Frac(a), DeterNum::Frac(b)) => DeterNum::Frac(a+b),
+            (DeterNum::Frac(a), DeterNum::Float(b)) => DeterNum::Float(a.to_f64()+b),
+            (DeterNum::Float(a), DeterNum::Frac(b)) => DeterNum::Float(a+b.to_f64()),
             _ => panic!("Vector can't add to float!")
         }
     }
@@ -35,6 +71,9 @@ impl std::ops::Sub for DeterNum {
         match (self, rhs) {
             (DeterNum::Float(a), DeterNum::Float(b)) => DeterNum::Float(a-b),
             (DeterNum::Vec(a), DeterNum::Vec(b)) => DeterNum::Vec(a-b),
+            (DeterNum::Frac(a), DeterNum::Frac(b)) => DeterNum::Frac(a-b),
+            (DeterNum::Frac(a), DeterNum::Float(b)) => DeterNum::Float(a.to_f64()-b),
+            (DeterNum::Float(a), DeterNum::Frac(b)) => DeterNum::Float(a-b.to_f64()),
             _ => panic!("Vector can't sub from or to float!")
         } 
     }
@@ -48,6 +87,11 @@ impl std::ops::Mul for DeterNum {
             (DeterNum::Vec(a), DeterNum::Vec(b)) => DeterNum::Float(a*b),
             (DeterNum::Float(a), DeterNum::Vec(b)) => DeterNum::Vec(b.strech(a)),
             (DeterNum::Vec(a), DeterNum::Float(b)) => DeterNum::Vec(a.strech(b)),
+            (DeterNum::Frac(a), DeterNum::Frac(b)) => DeterNum::Frac(a*b),
+            (DeterNum::Frac(a), DeterNum::Float(b)) => DeterNum::Float(a.to_f64()*b),
+            (DeterNum::Float(a), DeterNum::Frac(b)) => DeterNum::Float(a*b.to_f64()),
+            (DeterNum::Frac(a), DeterNum::Vec(b)) => DeterNum::Vec(b.strech(a.to_f64())),
+            (DeterNum::Vec(a), DeterNum::Frac(b)) => DeterNum::Vec(a.strech(b.to_f64())),
         } 
     }
 }
@@ -57,11 +101,25 @@ impl std::ops::Div for DeterNum {
     fn div(self, rhs: Self) -> Self::Output {
         match (self, rhs) {
             (DeterNum::Float(a), DeterNum::Float(b)) => DeterNum::Float(a/b),
+            (DeterNum::Frac(a), DeterNum::Frac(b)) => DeterNum::Frac(a/b),
+            (DeterNum::Frac(a), DeterNum::Float(b)) => DeterNum::Float(a.to_f64()/b),
+            (DeterNum::Float(a), DeterNum::Frac(b)) => DeterNum::Float(a/b.to_f64()),
             _ => panic!("Vector can't divide or be divided!")
         } 
     }
 }
 
+impl std::ops::Neg for DeterNum {
+    type Output = Self;
+    fn neg(self) -> Self::Output {
+        match self {
+            DeterNum::Float(a) => DeterNum::Float(-a),
+            DeterNum::Vec(a) => DeterNum::Vec(-a),
+            DeterNum::Frac(a) => DeterNum::Frac(-a)
+        }
+    }
+}
+
 impl std::ops::AddAssign for DeterNum {
     fn add_assign(&mut self, rhs: Self) {
         *self = self.clone() + rhs
@@ -109,6 +167,10 @@ impl Determinant {
         Self::from_vec_deter(buf)
     }
 
+    pub fn from_frac(val: Vec<Vec<Fraction>>) -> Self {
+        Self::from_vec_deter(val.into_iter().map(|i| i.into_iter().map(DeterNum::Frac).collect()).collect())
+    }
+
     pub fn from_vec_deter(val: Vec<Vec<DeterNum>>) -> Self {
         let out = Self { val };
         out.check_shape();
@@ -116,11 +178,62 @@ impl Determinant {
     }
 
     fn check_shape(&self) {
-        if self.val.len() != 0 && self.val.len() != self.val[0].len() {
+        if !self.val.is_empty() && self.val.len() != self.val[0].len() {
             panic!("This shape is not available to determinant!")
         }
     }
 
+    pub fn len(&self) -> usize {
+        self.val.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.val.is_empty()
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> &DeterNum {
+        &self.val[y][x]
+    }
+
+    // the determinant left after removing row y and column x
+    pub fn minor(&self, x: usize, y: usize) -> Self {
+        let mut buf = Vec::new();
+        for (row, i) in self.val.iter().enumerate() {
+            if row == y { continue }
+            let mut buf_1 = Vec::new();
+            for (col, j) in i.iter().enumerate() {
+                if col != x {
+                    buf_1.push(j.clone())
+                }
+            };
+            buf.push(buf_1)
+        };
+        Self { val: buf }
+    }
+
+    // (-1)^(x + y) times the minor at column x and row y
+    pub fn cofactor(&self, x: usize, y: usize) -> DeterNum {
+        let minor = self.minor(x, y).cal();
+        if (x + y).is_multiple_of(2) { minor } else { -minor }
+    }
+
+    // solve A x = b with A as this determinant, None when it is singular;
+    // exact entries give exact answers
+    pub fn solve_cramer(&self, rhs: &[DeterNum]) -> Option<Vec<DeterNum>> {
+        if rhs.len() != self.len() { panic!("Mismatched shapes!") }
+        let det = self.cal();
+        if det.is_zero() { return None }
+        let mut out = Vec::new();
+        for x in 0..self.len() {
+            let mut buf = self.clone();
+            for (y, val) in rhs.iter().enumerate() {
+                buf.change_place(x, y, val.clone())
+            };
+            out.push(buf.cal() / det.clone())
+        };
+        Some(out)
+    }
+
     pub fn change_place(&mut self, x: usize, y: usize, val: DeterNum) {
         self.val[y][x] = val
     }
@@ -135,8 +248,9 @@ impl Determinant {
 
     pub fn cal(&self) -> DeterNum {
 
-        if self.val.len() == 0 {
-            return DeterNum::Float(0.0)
+        // the empty product, so the cofactor of a 1x1 determinant is 1
+        if self.val.is_empty() {
+            return DeterNum::Float(1.0)
         } else if self.val.len() == 1 {
             return self.val[0][0].clone()
         } else if self.val.len() == 2 {
@@ -148,38 +262,27 @@ impl Determinant {
         let mut sub = Vec::new();
 
         for i in 0..self.val[0].len() {
-            let mut buf = Vec::new();
-            for row in 1..self.val.len() {
-                let mut buf_1 = Vec::new();
-                for col in 0..self.val[0].len() {
-                    if col != i {
-                        buf_1.push(self.val[row][col].clone());
-                    }
-                };
-                buf.push(buf_1)
-            };
-
-            let cal = self.val[0][i].clone() * Self::from_vec_deter(buf).cal();
+            let cal = self.val[0][i].clone() * self.minor(i, 0).cal();
             if i % 2 == 0 { add.push(cal) } else { sub.push(cal) }
         }
         
         let mut buf_add = add[0].clone();
-        for i in 1..add.len() {
-            buf_add += add[i].clone()
+        for i in add.iter().skip(1) {
+            buf_add += i.clone()
         };
 
         let mut buf_sub = sub[0].clone();
-        for i in 1..sub.len() {
-            buf_sub += sub[i].clone()
+        for i in sub.iter().skip(1) {
+            buf_sub += i.clone()
         };
 
-        return buf_add - buf_sub
+        buf_add - buf_sub
     }
 }
 
 impl std::fmt::Display for Determinant {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        if self.val.len() == 0 {
+        if self.val.is_empty() {
             return write!(f, "| |")
         }
 
@@ -205,9 +308,9 @@ impl std::fmt::Display for Determinant {
             let mut max_length = Vec::new();
             for i in 0..length[0].len() {
                 let mut max = 0;
-                for j in 0..length.len() {
-                    if length[j][i] > max {
-                        max = length[j][i]
+                for j in length.iter() {
+                    if j[i] > max {
+                        max = j[i]
                     }
                 }
                 max_length.push(max)
@@ -237,4 +340,59 @@ impl std::fmt::Display for Determinant {
 
         write!(f, "{}", words.join("\n"))
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lin_alg::{DEFAULT_ATOL, DEFAULT_RTOL};
+
+    fn assert_det(deter: Determinant, expected: f64) {
+        let val = deter.cal();
+        if !val.approx_eq(&DeterNum::Float(expected), DEFAULT_RTOL, DEFAULT_ATOL) {
+            panic!("determinant is {}, expected {}", val, expected)
+        }
+    }
+
+    #[test]
+    fn cal_2x2() {
+        assert_det(Determinant::from([[3.0, 8.0], [4.0, 6.0]]), -14.0);
+        assert_det(Determinant::from([[1.0, 0.0], [0.0, 1.0]]), 1.0)
+    }
+
+    #[test]
+    fn cal_3x3() {
+        assert_det(Determinant::from([[6.0, 1.0, 1.0], [4.0, -2.0, 5.0], [2.0, 8.0, 7.0]]), -306.0);
+        assert_det(Determinant::from([[2.0, 0.0, 0.0], [0.0, 3.0, 0.0], [0.0, 0.0, 4.0]]), 24.0)
+    }
+
+    #[test]
+    fn cal_4x4() {
+        assert_det(Determinant::from_i([[1, 0, 2, -1], [3, 0, 0, 5], [2, 1, 4, -3], [1, 0, 5, 0]]), 30.0)
+    }
+
+    #[test]
+    fn cal_empty() {
+        assert_det(Determinant::from_vec(Vec::new()), 1.0)
+    }
+
+    #[test]
+    fn cofactor_1x1() {
+        let matrix = Matrix::from([[5.0]]);
+        assert_det(Determinant::from_matrix(&matrix).minor(0, 0), 1.0);
+        crate::assert_matrix_close!(matrix.cofactor_matrix(), Matrix::from([[1.0]]));
+        crate::assert_matrix_close!(matrix.adjugate(), Matrix::from([[1.0]]))
+    }
+
+    #[test]
+    fn adjugate_3x3() {
+        let matrix = Matrix::from([[1.0, 2.0, 3.0], [0.0, 1.0, 4.0], [5.0, 6.0, 0.0]]);
+        let expected = Matrix::from([[-24.0, 18.0, 5.0], [20.0, -15.0, -4.0], [-5.0, 4.0, 1.0]]);
+        crate::assert_matrix_close!(matrix.adjugate(), expected)
+    }
+
+    #[test]
+    fn cal_singular() {
+        assert_det(Determinant::from([[1.0, 2.0], [2.0, 4.0]]), 0.0);
+        assert_det(Determinant::from([[1.0, 2.0, 3.0], [4.0, 5.0, 6.0], [7.0, 8.0, 9.0]]), 0.0)
+    }
+}
@@ -0,0 +1,189 @@
+use super::{DeterNum, Determinant};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Line {
+    Row(usize),
+    Col(usize)
+}
+
+#[derive(Clone, Debug)]
+pub struct ExpansionTerm {
+    pub row: usize,
+    pub col: usize,
+    // (-1)^(row + col)
+    pub sign: i8,
+    pub entry: DeterNum,
+    // None when the entry is zero and the minor is never evaluated
+    pub minor: Option<Expansion>,
+    // sign * entry * minor
+    pub partial: DeterNum
+}
+
+#[derive(Clone, Debug)]
+pub struct Expansion {
+    pub determinant: Determinant,
+    // None for determinants small enough to be worked out directly
+    pub line: Option<Line>,
+    pub terms: Vec<ExpansionTerm>,
+    pub value: DeterNum
+}
+
+impl Determinant {
+    // the row or column with the most zero entries, rows win ties
+    pub fn sparsest_line(&self) -> Line {
+        let n = self.len();
+        let mut best = (Line::Row(0), 0);
+        for i in 0..n {
+            let zeros = (0..n).filter(|j| self.get(*j, i).is_zero()).count();
+            if zeros > best.1 { best = (Line::Row(i), zeros) }
+        };
+        for i in 0..n {
+            let zeros = (0..n).filter(|j| self.get(i, *j).is_zero()).count();
+            if zeros > best.1 { best = (Line::Col(i), zeros) }
+        };
+        best.0
+    }
+
+    pub fn explain(&self) -> Expansion {
+        if self.len() <= 2 { return self.explain_direct() }
+        self.explain_along(self.sparsest_line())
+    }
+
+    // cofactor expansion along the given line, minors below use their own sparsest line
+    pub fn explain_along(&self, line: Line) -> Expansion {
+        let n = self.len();
+        let places = match line {
+            Line::Row(row) if row < n => (0..n).map(|col| (row, col)).collect::<Vec<_>>(),
+            Line::Col(col) if col < n => (0..n).map(|row| (row, col)).collect::<Vec<_>>(),
+            _ => panic!("The line is out of the determinant!")
+        };
+        if n <= 2 { return self.explain_direct() }
+        // a zero term can only be skipped when it is a plain number
+        let numeric = (0..n).all(|i| (0..n).all(|j| self.get(j, i).to_f64().is_some()));
+
+        let mut terms = Vec::new();
+        let mut value: Option<DeterNum> = None;
+        for (row, col) in places {
+            let sign = if (row + col) % 2 == 0 { 1 } else { -1 };
+            let entry = self.get(col, row).clone();
+            let (minor, partial) = if numeric && entry.is_zero() {
+                // the zero entry itself, so exact expansions stay exact
+                (None, entry.clone())
+            } else {
+                let minor = self.minor(col, row).explain();
+                let partial = entry.clone() * minor.value.clone();
+                (Some(minor), if sign > 0 { partial } else { -partial })
+            };
+            value = Some(match value {
+                Some(sum) => sum + partial.clone(),
+                None => partial.clone()
+            });
+            terms.push(ExpansionTerm { row, col, sign, entry, minor, partial })
+        };
+
+        Expansion { determinant: self.clone(), line: Some(line), terms, value: value.unwrap_or(DeterNum::Float(0.0)) }
+    }
+
+    // determinants up to 2x2 are worked out without an expansion
+    fn explain_direct(&self) -> Expansion {
+        Expansion { determinant: self.clone(), line: None, terms: Vec::new(), value: self.cal() }
+    }
+}
+
+impl Expansion {
+    fn write(&self, f: &mut std::fmt::Formatter<'_>, indent: &str) -> std::fmt::Result {
+        for i in format!("{}", self.determinant).lines() {
+            writeln!(f, "{}{}", indent, i)?
+        };
+        if let Some(line) = self.line {
+            // printed 1-based, the way it is written by hand
+            match line {
+                Line::Row(row) => writeln!(f, "{}expand along row {}", indent, row + 1)?,
+                Line::Col(col) => writeln!(f, "{}expand along column {}", indent, col + 1)?
+            };
+            let inner = format!("{}    ", indent);
+            for term in self.terms.iter() {
+                let sign = if term.sign > 0 { '+' } else { '-' };
+                writeln!(f, "{}  {} {} * M({}, {})", indent, sign, term.entry, term.row + 1, term.col + 1)?;
+                match &term.minor {
+                    Some(minor) => minor.write(f, &inner)?,
+                    None => writeln!(f, "{}the entry is zero, the term vanishes", inner)?
+                };
+                writeln!(f, "{}partial: {}", inner, term.partial)?
+            }
+        }
+        writeln!(f, "{}= {}", indent, self.value)
+    }
+}
+
+impl std::fmt::Display for Expansion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.write(f, "")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sum(expansion: &Expansion) -> f64 {
+        expansion.terms.iter().map(|i| i.partial.to_f64().unwrap()).sum()
+    }
+
+    #[test]
+    fn terms_sum_to_the_determinant() {
+        let deter = Determinant::from([[2.0, -1.0, 3.0], [1.0, 4.0, 0.0], [5.0, 2.0, -2.0]]);
+        let value = deter.cal().to_f64().unwrap();
+        let out = deter.explain_along(Line::Row(0));
+        assert_eq!(out.line, Some(Line::Row(0)));
+        assert_eq!(out.terms.iter().map(|i| (i.row, i.col, i.sign)).collect::<Vec<_>>(), vec![(0, 0, 1), (0, 1, -1), (0, 2, 1)]);
+        // 2 * (-8) + 1 * (-2) + 3 * (-18)
+        assert_eq!(out.terms.iter().map(|i| i.partial.to_f64().unwrap()).collect::<Vec<_>>(), vec![-16.0, -2.0, -54.0]);
+        assert_eq!(sum(&out), value);
+        assert_eq!(out.value.to_f64(), Some(value));
+        // the minors are 2x2 and worked out directly
+        let minor = out.terms[0].minor.as_ref().unwrap();
+        assert_eq!((minor.line, minor.terms.len(), minor.value.to_f64()), (None, 0, Some(-8.0)))
+    }
+
+    #[test]
+    fn expansion_along_a_column() {
+        let deter = Determinant::from([[1.0, 0.0, 2.0, -1.0], [3.0, 0.0, 0.0, 5.0], [2.0, 1.0, 4.0, -3.0], [1.0, 0.0, 5.0, 0.0]]);
+        let value = deter.cal().to_f64().unwrap();
+        let out = deter.explain_along(Line::Col(2));
+        assert_eq!(out.terms.iter().map(|i| (i.row, i.col, i.sign)).collect::<Vec<_>>(), vec![(0, 2, 1), (1, 2, -1), (2, 2, 1), (3, 2, -1)]);
+        // the zero entry is skipped without a minor
+        assert!(out.terms[1].minor.is_none());
+        assert!((sum(&out) - value).abs() < 1e-9);
+        // the second column has a single entry and is picked by explain
+        assert_eq!(deter.sparsest_line(), Line::Col(1));
+        let out = deter.explain();
+        assert_eq!(out.terms.iter().filter(|i| i.minor.is_some()).count(), 1);
+        assert!((out.value.to_f64().unwrap() - value).abs() < 1e-9)
+    }
+
+    #[test]
+    fn small_determinants_are_worked_out_directly() {
+        let out = Determinant::from([[1.0, 2.0], [3.0, 4.0]]).explain_along(Line::Col(1));
+        assert_eq!((out.line, out.terms.len(), out.value.to_f64()), (None, 0, Some(-2.0)));
+        assert_eq!(Determinant::from_vec(Vec::new()).explain().value.to_f64(), Some(1.0))
+    }
+
+    #[test]
+    #[should_panic(expected = "The line is out of the determinant!")]
+    fn line_out_of_range() {
+        Determinant::from([[1.0, 2.0, 3.0], [4.0, 5.0, 6.0], [7.0, 8.0, 10.0]]).explain_along(Line::Row(3));
+    }
+
+    #[test]
+    #[should_panic(expected = "The line is out of the determinant!")]
+    fn line_out_of_range_of_a_small_determinant() {
+        Determinant::from([[1.0, 2.0], [3.0, 4.0]]).explain_along(Line::Col(2));
+    }
+
+    #[test]
+    #[should_panic(expected = "The line is out of the determinant!")]
+    fn line_out_of_range_of_an_empty_determinant() {
+        Determinant::from_vec(Vec::new()).explain_along(Line::Row(0));
+    }
+}
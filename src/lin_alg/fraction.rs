@@ -0,0 +1,132 @@
+// an exact rational number, always kept in lowest terms with a positive denominator
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Fraction {
+    num: i64,
+    den: i64
+}
+
+fn gcd(a: i64, b: i64) -> i64 {
+    let (mut a, mut b) = (a.abs(), b.abs());
+    while b != 0 {
+        (a, b) = (b, a % b)
+    };
+    a
+}
+
+// None when it doesn't fit in an i64
+fn lcm(a: i64, b: i64) -> Option<i64> {
+    (a / gcd(a, b)).checked_mul(b)
+}
+
+impl Fraction {
+    pub fn new(num: i64, den: i64) -> Self {
+        if den == 0 { panic!("The denominator can't be zero!") }
+        let div = gcd(num, den).max(1) * den.signum();
+        Self { num: num / div, den: den / div }
+    }
+
+    pub fn from_int(val: i64) -> Self {
+        Self { num: val, den: 1 }
+    }
+
+    pub fn num(self) -> i64 {
+        self.num
+    }
+
+    pub fn den(self) -> i64 {
+        self.den
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.num == 0
+    }
+
+    pub fn to_f64(self) -> f64 {
+        self.num as f64 / self.den as f64
+    }
+
+    // the numerator once the denominator is scaled up to `den`, a multiple of it
+    fn num_over(self, den: i64) -> Option<i64> {
+        self.num.checked_mul(den / self.den)
+    }
+
+    fn checked(num: Option<i64>, den: Option<i64>) -> Self {
+        match (num, den) {
+            (Some(num), Some(den)) => Self::new(num, den),
+            _ => panic!("Fraction overflow!")
+        }
+    }
+}
+
+impl std::fmt::Display for Fraction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.den == 1 {
+            write!(f, "{}", self.num)
+        } else {
+            write!(f, "{}/{}", self.num, self.den)
+        }
+    }
+}
+
+impl std::ops::Add for Fraction {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self::Output {
+        let den = lcm(self.den, rhs.den);
+        let num = den.and_then(|den| {
+            self.num_over(den).zip(rhs.num_over(den)).and_then(|(a, b)| a.checked_add(b))
+        });
+        Self::checked(num, den)
+    }
+}
+
+impl std::ops::Sub for Fraction {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self::Output {
+        self + -rhs
+    }
+}
+
+impl std::ops::Mul for Fraction {
+    type Output = Self;
+    fn mul(self, rhs: Self) -> Self::Output {
+        // cross-cancel first to keep the numbers small
+        let a = gcd(self.num, rhs.den).max(1);
+        let b = gcd(rhs.num, self.den).max(1);
+        Self::checked(
+            (self.num / a).checked_mul(rhs.num / b),
+            (self.den / b).checked_mul(rhs.den / a)
+        )
+    }
+}
+
+impl std::ops::Div for Fraction {
+    type Output = Self;
+    fn div(self, rhs: Self) -> Self::Output {
+        if rhs.num == 0 { panic!("Can't divide by zero!") }
+        self * Self::new(rhs.den, rhs.num)
+    }
+}
+
+impl std::ops::Neg for Fraction {
+    type Output = Self;
+    fn neg(self) -> Self::Output {
+        Self { num: -self.num, den: self.den }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lowest_terms() {
+        assert_eq!(Fraction::new(1, 6) + Fraction::new(1, 3), Fraction::new(1, 2));
+        assert_eq!(Fraction::new(2, -4), Fraction::new(-1, 2))
+    }
+
+    #[test]
+    #[should_panic(expected = "Fraction overflow!")]
+    fn add_overflow() {
+        let _ = Fraction::new(1, i64::MAX) + Fraction::new(1, i64::MAX - 1);
+    }
+}
@@ -1,13 +1,32 @@
-use crate::func::Tanh;
-
-use super::{Determinant, Vector, DeterNum, func};
+use super::{Determinant, Vector, DeterNum, MatrixDisplay, PrintOptions, func};
+use func::Softmax;
 
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(try_from = "RawMatrix"))]
 pub struct Matrix {
     vec: Vec<Vector>,
     shape: (usize, usize)
 }
 
+// the unchecked form a deserializer hands over, validated before it becomes a Matrix
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct RawMatrix {
+    vec: Vec<Vector>,
+    shape: (usize, usize)
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<RawMatrix> for Matrix {
+    type Error = String;
+    fn try_from(raw: RawMatrix) -> Result<Self, Self::Error> {
+        if raw.vec.len() != raw.shape.1 || raw.vec.iter().any(|i| i.len() != raw.shape.0) {
+            return Err(format!("rows do not match the shape {:?}", raw.shape))
+        }
+        Ok(Self { vec: raw.vec, shape: raw.shape })
+    }
+}
+
 impl Matrix {
     pub fn new(shape: (usize, usize)) -> Self {
         let mut buf = Vec::new();
@@ -20,18 +39,24 @@ impl Matrix {
         }
     }
 
+    pub fn zeros(shape: (usize, usize)) -> Self {
+        let mut out = Matrix::new(shape);
+        out.oper_assign(&|_| 0.0);
+        out
+    }
+
     pub fn from<const X: usize, const Y: usize>(val: [[f64; X]; Y]) -> Self {
         let mut buf = Vec::new();
-        for i in 0..Y {
-            buf.push(Vector::from(val[i]))
+        for i in val.iter() {
+            buf.push(Vector::from(*i))
         }
         Self { vec: buf, shape: (X, Y) }
     }
 
     pub fn from_i<const X: usize, const Y: usize>(val: [[isize; X]; Y]) -> Self {
         let mut buf = Vec::new();
-        for i in 0..Y {
-            buf.push(Vector::from_i(val[i]))
+        for i in val.iter() {
+            buf.push(Vector::from_i(*i))
         }
         Self { vec: buf, shape: (X, Y) }
     }
@@ -74,7 +99,7 @@ impl Matrix {
         self.shape == rhs.shape
     }
 
-    pub fn oper(&self, op: &(dyn Fn(f64) -> f64)) -> Self {
+    pub fn oper(&self, op: &dyn Fn(f64) -> f64) -> Self {
         let mut out = self.clone();
         for i in out.vec.iter_mut() {
             *i = i.oper(op)
@@ -82,13 +107,13 @@ impl Matrix {
         out
     }
 
-    pub fn oper_assign(&mut self, op: &(dyn Fn(f64) -> f64)) {
+    pub fn oper_assign(&mut self, op: &dyn Fn(f64) -> f64) {
         for i in self.vec.iter_mut() {
             i.oper_assign(op)
         }
     }
 
-    pub fn oper_with(&self, rhs: Matrix, op: &(dyn Fn(f64, f64)-> f64)) -> Self {
+    pub fn oper_with(&self, rhs: Matrix, op: &dyn Fn(f64, f64) -> f64) -> Self {
         if ! self.same_shape(&rhs) { panic!("The shapes are nut matched!") }
         let mut out = self.clone();
         for i in 0..self.shape.1 {
@@ -97,7 +122,7 @@ impl Matrix {
         out
     }
 
-    pub fn oper_with_assign(&mut self, rhs: Matrix, op: &(dyn Fn(f64, f64)-> f64)) {
+    pub fn oper_with_assign(&mut self, rhs: Matrix, op: &dyn Fn(f64, f64) -> f64) {
         if ! self.same_shape(&rhs) { panic!("The shapes are nut matched!") }
         for i in 0..self.shape.1 {
             self.vec[i].oper_with_assign(rhs.vec[i].clone(), op);
@@ -123,8 +148,8 @@ impl Matrix {
 
     pub fn change_col<const Y: usize>(&mut self, x: usize, val: [f64; Y]) {
         if ! Y == self.shape.1 { panic!("The shapes are not matched!") }
-        for i in 0..self.vec.len() {
-            self.vec[i].get_mut_val()[x] = val[i]
+        for (row, i) in self.vec.iter_mut().zip(val.iter()) {
+            row.get_mut_val()[x] = *i
         }
     }
 
@@ -162,7 +187,7 @@ impl Matrix {
     }
 
     pub fn kron_product(&self, rhs: Matrix) -> Self {
-        let mut out = Matrix::new((self.shape.0 * rhs.shape.0, self.shape.1 * self.shape.1));
+        let mut out = Matrix::new((self.shape.0 * rhs.shape.0, self.shape.1 * rhs.shape.1));
         for i in 0..out.shape.0 {
             for j in 0..out.shape.1 {
                 let a = (i / rhs.shape.0, j / rhs.shape.1);
@@ -174,13 +199,67 @@ impl Matrix {
     }
 
     pub fn kron_product_assign(&mut self, rhs: Matrix) {
-        for i in 0..self.shape.0 {
-            for j in 0..self.shape.1 {
-                let a = (i / rhs.shape.0, j / rhs.shape.1);
-                let b = (i % rhs.shape.0, j % rhs.shape.1);
-                self.change_place((i, j), self.get(a.0, a.1) * rhs.get(b.0, b.1))
+        *self = self.kron_product(rhs)
+    }
+
+    // A ⊕ B = A ⊗ I + I ⊗ B, both sides have to be square
+    pub fn kron_sum(&self, rhs: Matrix) -> Self {
+        if self.shape.0 != self.shape.1 || rhs.shape.0 != rhs.shape.1 { panic!("Kronecker sum needs square matrices!") }
+        self.kron_product(Matrix::identity(rhs.shape.0)) + Matrix::identity(self.shape.0).kron_product(rhs)
+    }
+
+    pub fn identity(size: usize) -> Self {
+        let mut out = Matrix::zeros((size, size));
+        for i in 0..size {
+            out.change_place((i, i), 1.0)
+        };
+        out
+    }
+
+    // blocks[i][j] is the block at block row i and block column j
+    pub fn from_blocks(blocks: Vec<Vec<Matrix>>) -> Self {
+        if blocks.is_empty() { return Matrix::zeros((0, 0)) }
+        let widths = blocks[0].iter().map(|i| i.shape.0).collect::<Vec<_>>();
+        let mut heights = Vec::new();
+        for row in blocks.iter() {
+            if row.len() != widths.len() { panic!("Every block row needs the same number of blocks!") }
+            for (block, width) in row.iter().zip(widths.iter()) {
+                if block.shape.0 != *width || block.shape.1 != row[0].shape.1 { panic!("The block shapes are not matched!") }
+            };
+            heights.push(row.first().map_or(0, |i| i.shape.1))
+        };
+
+        let mut out = Matrix::zeros((widths.iter().sum(), heights.iter().sum()));
+        let mut y = 0;
+        for (row, height) in blocks.iter().zip(heights.iter()) {
+            let mut x = 0;
+            for block in row.iter() {
+                out.set_block((x, y), block);
+                x += block.shape.0
             };
+            y += height
         };
+        out
+    }
+
+    pub fn block_diag(blocks: &[Matrix]) -> Self {
+        let mut out = Matrix::zeros((blocks.iter().map(|i| i.shape.0).sum(), blocks.iter().map(|i| i.shape.1).sum()));
+        let mut place = (0, 0);
+        for block in blocks.iter() {
+            out.set_block(place, block);
+            place = (place.0 + block.shape.0, place.1 + block.shape.1)
+        };
+        out
+    }
+
+    // copy `block` in with its top left corner at `place`
+    pub fn set_block(&mut self, place: (usize, usize), block: &Matrix) {
+        if place.0 + block.shape.0 > self.shape.0 || place.1 + block.shape.1 > self.shape.1 { panic!("The block does not fit!") }
+        for (y, row) in block.iter().enumerate() {
+            for (x, val) in row.iter().enumerate() {
+                self.change_place((place.0 + x, place.1 + y), *val)
+            }
+        }
     }
 
     pub fn strech(&self, times: f64) -> Self {
@@ -225,6 +304,20 @@ impl Matrix {
         max
     }
 
+    pub fn first_mismatch(&self, rhs: &Matrix, rtol: f64, atol: f64) -> Option<((usize, usize), f64, f64)> {
+        if ! self.same_shape(rhs) { panic!("The shapes are not matched!") }
+        for (y, (a, b)) in self.vec.iter().zip(rhs.vec.iter()).enumerate() {
+            if let Some((x, a, b)) = a.first_mismatch(b, rtol, atol) {
+                return Some(((x, y), a, b))
+            }
+        };
+        None
+    }
+
+    pub fn approx_eq(&self, rhs: &Matrix, rtol: f64, atol: f64) -> bool {
+        self.same_shape(rhs) && self.first_mismatch(rhs, rtol, atol).is_none()
+    }
+
     pub fn get_determinant(&self) -> Determinant {
         Determinant::from_matrix(self)
     }
@@ -234,10 +327,42 @@ impl Matrix {
         print!("{}", deter);
         match deter.cal() {
             DeterNum::Float(num) => num,
-            DeterNum::Vec(_) => panic!("Shouldn't be here.")
+            _ => panic!("Shouldn't be here.")
         }
     }
 
+    // the minor at column x and row y, following the (x, y) order of `get`
+    pub fn minor(&self, x: usize, y: usize) -> f64 {
+        self.square_determinant().minor(x, y).cal().to_f64().expect("Shouldn't be here.")
+    }
+
+    pub fn cofactor_matrix(&self) -> Self {
+        let deter = self.square_determinant();
+        let mut out = Matrix::zeros(self.shape);
+        for y in 0..self.shape.1 {
+            for x in 0..self.shape.0 {
+                out.change_place((x, y), deter.cofactor(x, y).to_f64().expect("Shouldn't be here."))
+            }
+        };
+        out
+    }
+
+    pub fn adjugate(&self) -> Self {
+        self.cofactor_matrix().t()
+    }
+
+    // None when the matrix is singular
+    pub fn solve_cramer(&self, rhs: &Vector) -> Option<Vector> {
+        let rhs = rhs.iter().map(|i| DeterNum::Float(*i)).collect::<Vec<_>>();
+        let out = self.square_determinant().solve_cramer(&rhs)?;
+        Some(Vector::from_vec(out.iter().map(|i| i.to_f64().expect("Shouldn't be here.")).collect()))
+    }
+
+    fn square_determinant(&self) -> Determinant {
+        if self.shape.0 != self.shape.1 { panic!("The matrix is not square!") }
+        self.get_determinant()
+    }
+
     pub fn t(&self) -> Self {
         let mut buf = Vec::new();
         for i in 0..self.shape.0 {
@@ -254,10 +379,24 @@ impl Matrix {
         *self = self.t()
     }
 
-    pub fn exp(&self) -> Self {
+    // e^x of every element, see `expm` for the matrix exponential
+    pub fn exp_elementwise(&self) -> Self {
         self.oper(&|i| {i.exp()})
     }
 
+    #[deprecated(note = "element-wise, use `exp_elementwise` or `expm` for the matrix exponential")]
+    pub fn exp(&self) -> Self {
+        self.exp_elementwise()
+    }
+
+    fn by_cols(&self, op: &dyn Fn(&Vector) -> Vector) -> Self {
+        Matrix::from_vec(self.t().iter().map(|i| op(i).get_val().clone()).collect()).t()
+    }
+
+    pub fn display_with(&self, options: PrintOptions) -> MatrixDisplay<'_> {
+        MatrixDisplay { matrix: self, options }
+    }
+
     pub fn restrict(&mut self, down: f64, up: f64) {
         if down >= up { panic!("Unaccpetable range!") }
         let max = self.max();
@@ -270,7 +409,19 @@ impl Matrix {
 
 impl std::fmt::Display for Matrix {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Matrix[\n    {}\n]", self.vec.iter().map(|a| {format!("{}", a)}).collect::<Vec<_>>().join("\n    "))
+        let options = PrintOptions { precision: f.precision(), ..Default::default() };
+        write!(f, "{}", self.display_with(options))
+    }
+}
+
+impl std::fmt::Debug for Matrix {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let options = PrintOptions { precision: f.precision(), align: false, ..Default::default() };
+        let rows = options.words(self).into_iter().map(|row| match row {
+            Some(row) => format!("[{}]", row.join(", ")),
+            None => "...".to_string()
+        }).collect::<Vec<_>>();
+        write!(f, "Matrix {{ shape: {:?}, val: [{}] }}", self.shape, rows.join(", "))
     }
 }
 
@@ -337,80 +488,140 @@ impl std::ops::DivAssign for Matrix {
     }
 }
 
+// every column is a separate distribution over the rows
 impl func::Softmax for Matrix {
     fn soft_for(&self) -> Self {
-        let mut out = Matrix::new(self.shape);
-        let exp = self.exp();
-
-        let mut exp_sum = Vec::new();
-        for i in 0..self.vec[0].len() {
-            let mut sum = 0.0;
-            for j in 0..self.vec.len() {
-                sum += exp.get(i, j)
-            };
-            exp_sum.push(sum)
-        };
+        self.soft_for_temp(1.0)
+    }
 
-        for i in 0..self.vec.len() {
-            for j in 0..self.vec[0].len() {
-                let val = exp.get(j, i) / exp_sum[j];
-                out.change_place((j, i), val)
-            }
-        };
-        out
+    fn soft_for_temp(&self, temperature: f64) -> Self {
+        self.by_cols(&|i| i.soft_for_temp(temperature))
     }
-}
 
-impl func::Sigmoid for Matrix {
-    fn sig_for(&self) -> Self {
-        let mut out = self.clone();
-        for i in out.iter_mut() {
-            *i = i.clone().sig_for()
-        };
-        out
+    fn log_soft_for(&self) -> Self {
+        self.by_cols(&|i| i.log_soft_for())
     }
 
-    fn sig_back(&self) -> Self {
-        let mut out = self.clone();
-        for i in out.iter_mut() {
-            *i = i.clone().sig_back()
-        };
-        out
+    fn soft_back(&self, grad: &Self) -> Self {
+        if ! self.same_shape(grad) { panic!("The shapes are not matched!") }
+        let cols = self.t().iter().zip(grad.t().iter()).map(|(i, j)| i.soft_back(j).get_val().clone()).collect();
+        Matrix::from_vec(cols).t()
     }
 }
 
-impl func::Relu for Matrix {
-    fn relu_for(&self) -> Self {
-        let mut out = self.clone();
-        for i in out.iter_mut() {
-            *i = i.clone().relu_for()
-        };
-        out
+impl func::Activation for Matrix {
+    fn forward(&self, kind: func::ActivationKind) -> Self {
+        match kind {
+            func::ActivationKind::Softmax => self.soft_for(),
+            _ => self.oper(&|i| { kind.apply(i) })
+        }
     }
 
-    fn relu_back(&self) -> Self {
-        let mut out = self.clone();
-        for i in out.iter_mut() {
-            *i = i.clone().relu_back()
-        };
-        out
+    fn derivative(&self, kind: func::ActivationKind) -> Self {
+        self.oper(&|i| { i.derivative(kind) })
     }
-}
 
-impl func::Tanh for Matrix {
-    fn tanh_for(&self) -> Self {
-        let mut out = self.clone();
-        for i in out.iter_mut() {
-            *i = i.clone().tanh_for();
-        };
-        out
+    fn backward(&self, kind: func::ActivationKind, grad: &Self) -> Self {
+        match kind {
+            func::ActivationKind::Softmax => self.soft_back(grad),
+            _ => self.derivative(kind).had_product(grad.clone())
+        }
     }
+}
 
-    fn tanh_back(&self) -> Self {
-        let mut out = self.clone();
-        for i in out.iter_mut() {
-            *i = i.clone().tanh_back()
-        };
-        out
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gradcheck::gradcheck;
+    use crate::gradcheck::testing::{dot, sample};
+
+    #[test]
+    fn kron_product_non_square() {
+        // 2 rows x 3 cols times 3 rows x 2 cols
+        let a = Matrix::from([[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]);
+        let b = Matrix::from([[0.0, 1.0], [2.0, 3.0], [4.0, 5.0]]);
+        let expected = Matrix::from([
+            [0.0, 1.0, 0.0, 2.0, 0.0, 3.0],
+            [2.0, 3.0, 4.0, 6.0, 6.0, 9.0],
+            [4.0, 5.0, 8.0, 10.0, 12.0, 15.0],
+            [0.0, 4.0, 0.0, 5.0, 0.0, 6.0],
+            [8.0, 12.0, 10.0, 15.0, 12.0, 18.0],
+            [16.0, 20.0, 20.0, 25.0, 24.0, 30.0]
+        ]);
+        crate::assert_matrix_close!(a.kron_product(b), expected);
+
+        // a row times a column gives the outer product laid out as a 2 x 3 matrix
+        let out = Matrix::from([[1.0, 2.0, 3.0]]).kron_product(Matrix::from([[1.0], [-1.0]]));
+        crate::assert_matrix_close!(out, Matrix::from([[1.0, 2.0, 3.0], [-1.0, -2.0, -3.0]]))
+    }
+
+    #[test]
+    fn kron_sum_of_different_sizes() {
+        let a = Matrix::from([[2.0]]);
+        let b = Matrix::from([[1.0, 2.0], [3.0, 4.0]]);
+        crate::assert_matrix_close!(a.kron_sum(b), Matrix::from([[3.0, 2.0], [3.0, 6.0]]))
+    }
+
+    #[test]
+    #[should_panic(expected = "Kronecker sum needs square matrices!")]
+    fn kron_sum_non_square() {
+        Matrix::from([[1.0, 2.0]]).kron_sum(Matrix::identity(2));
+    }
+
+    #[test]
+    fn blocks() {
+        let out = Matrix::from_blocks(vec![
+            vec![Matrix::from([[1.0, 2.0]]), Matrix::from([[3.0]])],
+            vec![Matrix::from([[4.0, 5.0], [6.0, 7.0]]), Matrix::from([[8.0], [9.0]])]
+        ]);
+        crate::assert_matrix_close!(out, Matrix::from([[1.0, 2.0, 3.0], [4.0, 5.0, 8.0], [6.0, 7.0, 9.0]]));
+        let out = Matrix::block_diag(&[Matrix::from([[1.0, 2.0]]), Matrix::from([[3.0], [4.0]])]);
+        crate::assert_matrix_close!(out, Matrix::from([[1.0, 2.0, 0.0], [0.0, 0.0, 3.0], [0.0, 0.0, 4.0]]))
+    }
+
+    #[test]
+    #[should_panic(expected = "The block shapes are not matched!")]
+    fn from_blocks_mismatched_heights() {
+        Matrix::from_blocks(vec![vec![Matrix::from([[1.0]]), Matrix::from([[2.0], [3.0]])]]);
+    }
+
+    #[test]
+    #[should_panic(expected = "The block shapes are not matched!")]
+    fn from_blocks_mismatched_widths() {
+        Matrix::from_blocks(vec![vec![Matrix::from([[1.0, 2.0]])], vec![Matrix::from([[3.0]])]]);
+    }
+
+    #[test]
+    fn set_block_fits_at_the_edge() {
+        let mut out = Matrix::zeros((3, 2));
+        out.set_block((1, 1), &Matrix::from([[5.0, 6.0]]));
+        crate::assert_matrix_close!(out, Matrix::from([[0.0, 0.0, 0.0], [0.0, 5.0, 6.0]]))
+    }
+
+    #[test]
+    #[should_panic(expected = "The block does not fit!")]
+    fn set_block_out_of_bounds() {
+        Matrix::zeros((3, 2)).set_block((2, 0), &Matrix::from([[5.0, 6.0]]));
+    }
+
+    #[test]
+    fn softmax_by_columns() {
+        let logits = Matrix::from([[1.0, 1000.0], [2.0, -1000.0], [3.0, 0.0]]);
+        let (_, col_sum) = logits.soft_for().sum();
+        crate::assert_vector_close!(Vector::from_vec(col_sum), Vector::from([1.0, 1.0]));
+        let cols = logits.soft_for().t();
+        crate::assert_vector_close!(cols.iter().next().unwrap(), Vector::from([1.0, 2.0, 3.0]).soft_for())
+    }
+
+    #[test]
+    fn softmax_backward() {
+        let logits = sample((3, 4), 6, 3.0);
+        let grad = sample((3, 4), 7, 1.0);
+        let check = gradcheck(|z| dot(&z.soft_for(), &grad), &logits, &logits.soft_back(&grad));
+        assert!(check.passed(1e-5, 1e-7), "soft_back: {}", check);
+        // through a temperature the gradient is scaled by 1 / T
+        let check = gradcheck(|z| dot(&z.soft_for_temp(2.0), &grad), &logits, &logits.strech(0.5).soft_back(&grad).strech(0.5));
+        assert!(check.passed(1e-5, 1e-7), "soft_back at temperature 2: {}", check)
     }
-}
\ No newline at end of file
+}
+
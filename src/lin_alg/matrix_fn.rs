@@ -0,0 +1,246 @@
+use super::Matrix;
+
+// coefficients of the [13/13] Padé approximant of e^x, Higham (2005)
+const PADE_13: [f64; 14] = [
+    64764752532480000.0, 32382376266240000.0, 7771770303897600.0, 1187353796428800.0,
+    129060195264000.0, 10559470521600.0, 670442572800.0, 33522128640.0,
+    1323241920.0, 40840800.0, 960960.0, 16380.0, 182.0, 1.0
+];
+// the largest 1-norm the [13/13] approximant handles to double precision
+const THETA_13: f64 = 5.371920351148152;
+// nodes of the partial fraction Padé approximant of log(I + X)
+const LOG_NODES: usize = 8;
+const MAX_ITER: usize = 100;
+
+// Gauss-Legendre nodes and weights moved onto [0, 1]
+fn gauss_legendre(n: usize) -> Vec<(f64, f64)> {
+    let mut out = Vec::new();
+    for i in 0..n {
+        let mut x = (std::f64::consts::PI * (i as f64 + 0.75) / (n as f64 + 0.5)).cos();
+        let mut diff = 0.0;
+        for _ in 0..MAX_ITER {
+            // P_n(x) and P_n'(x) by the three-term recurrence
+            let (mut p, mut p_prev) = (1.0, 0.0);
+            for j in 1..=n {
+                (p, p_prev) = (((2 * j - 1) as f64 * x * p - (j - 1) as f64 * p_prev) / j as f64, p)
+            };
+            diff = n as f64 * (x * p - p_prev) / (x * x - 1.0);
+            let step = p / diff;
+            x -= step;
+            if step.abs() < 1e-15 { break }
+        };
+        out.push(((1.0 + x) / 2.0, 1.0 / ((1.0 - x * x) * diff * diff)))
+    };
+    out
+}
+
+impl Matrix {
+    fn square_size(&self) -> usize {
+        if self.shape().0 != self.shape().1 { panic!("The matrix is not square!") }
+        self.shape().0
+    }
+
+    // the largest absolute column sum
+    pub fn norm_1(&self) -> f64 {
+        let (x, y) = self.shape();
+        (0..x).map(|i| (0..y).map(|j| self.get(i, j).abs()).sum::<f64>()).fold(0.0, f64::max)
+    }
+
+    // self^-1 * rhs by Gauss-Jordan elimination with partial pivoting, None when self is singular
+    fn left_divide(&self, rhs: &Matrix) -> Option<Self> {
+        let n = self.square_size();
+        if rhs.shape().1 != n { panic!("Shape Mismatch") }
+        let tol = self.norm_1() * n as f64 * f64::EPSILON;
+        let mut a = self.get_val();
+        let mut b = rhs.get_val();
+        for col in 0..n {
+            let pivot = (col..n).max_by(|i, j| a[*i][col].abs().total_cmp(&a[*j][col].abs()))?;
+            if a[pivot][col].abs() <= tol { return None }
+            a.swap(col, pivot);
+            b.swap(col, pivot);
+            let (a_pivot, b_pivot) = (a[col].clone(), b[col].clone());
+            for row in 0..n {
+                let factor = a[row][col] / a_pivot[col];
+                if row == col || factor == 0.0 { continue }
+                for (i, j) in a[row].iter_mut().zip(a_pivot.iter()).skip(col) {
+                    *i -= factor * j
+                };
+                for (i, j) in b[row].iter_mut().zip(b_pivot.iter()) {
+                    *i -= factor * j
+                }
+            }
+        };
+        for (index, row) in b.iter_mut().enumerate() {
+            for i in row.iter_mut() {
+                *i /= a[index][index]
+            }
+        };
+        Some(Matrix::from_vec(b))
+    }
+
+    // None when the matrix is singular
+    pub fn inverse(&self) -> Option<Self> {
+        self.left_divide(&Matrix::identity(self.square_size()))
+    }
+
+    // scaling and squaring with the [13/13] Padé approximant
+    pub fn expm(&self) -> Self {
+        let n = self.square_size();
+        let norm = self.norm_1();
+        let s = if norm > THETA_13 { (norm / THETA_13).log2().ceil() as i32 } else { 0 };
+        let a = self.strech(0.5f64.powi(s));
+        let b = PADE_13;
+
+        let identity = Matrix::identity(n);
+        let a2 = a.clone() * a.clone();
+        let a4 = a2.clone() * a2.clone();
+        let a6 = a4.clone() * a2.clone();
+        let u = a * (
+            a6.clone() * (a6.strech(b[13]) + a4.strech(b[11]) + a2.strech(b[9]))
+            + a6.strech(b[7]) + a4.strech(b[5]) + a2.strech(b[3]) + identity.strech(b[1])
+        );
+        let v = a6.clone() * (a6.strech(b[12]) + a4.strech(b[10]) + a2.strech(b[8]))
+            + a6.strech(b[6]) + a4.strech(b[4]) + a2.strech(b[2]) + identity.strech(b[0]);
+
+        let mut out = (v.clone() - u.clone()).left_divide(&(v + u)).expect("The matrix is too large to exponentiate!");
+        for _ in 0..s {
+            out = out.clone() * out
+        };
+        out
+    }
+
+    // the principal square root by the product form of the Denman-Beavers iteration,
+    // None when it doesn't exist, e.g. for singular matrices or negative eigenvalues
+    pub fn sqrtm(&self) -> Option<Self> {
+        let n = self.square_size();
+        let identity = Matrix::identity(n);
+        let mut m = self.clone();
+        let mut y = self.clone();
+        for _ in 0..MAX_ITER {
+            let m_inv = m.inverse()?;
+            y *= (identity.clone() + m_inv.clone()).strech(0.5);
+            m = (identity.clone() + (m + m_inv).strech(0.5)).strech(0.5);
+            if (m.clone() - identity.clone()).norm_1() <= n as f64 * f64::EPSILON {
+                return Some(y)
+            }
+        };
+        None
+    }
+
+    // the principal logarithm by inverse scaling and squaring, None when it doesn't exist
+    pub fn logm(&self) -> Option<Self> {
+        let n = self.square_size();
+        let identity = Matrix::identity(n);
+        let mut x = self.clone();
+        let mut k = 0;
+        // log(A) = 2^k log(A^(1/2^k)), and A^(1/2^k) gets close to I
+        while (x.clone() - identity.clone()).norm_1() > 0.25 {
+            if k == MAX_ITER { return None }
+            x = x.sqrtm()?;
+            k += 1
+        };
+        let x = x - identity.clone();
+
+        // log(I + X) = X (I + tX)^-1 integrated over t in [0, 1]
+        let mut out = Matrix::zeros((n, n));
+        for (node, weight) in gauss_legendre(LOG_NODES) {
+            out += (identity.clone() + x.strech(node)).left_divide(&x)?.strech(weight)
+        };
+        Some(out.strech(2f64.powi(k as i32)))
+    }
+
+    // repeated squaring, negative powers go through the inverse and are None for singular matrices
+    pub fn powi(&self, n: i32) -> Option<Self> {
+        let size = self.square_size();
+        let mut base = if n < 0 { self.inverse()? } else { self.clone() };
+        let mut n = n.unsigned_abs();
+        let mut out = Matrix::identity(size);
+        while n > 0 {
+            if n & 1 == 1 { out *= base.clone() }
+            base = base.clone() * base;
+            n >>= 1
+        };
+        Some(out)
+    }
+
+    // the principal power exp(p log(A)), which for a diagonalizable matrix is V diag(λ^p) V^-1,
+    // None when the logarithm doesn't exist
+    pub fn powf(&self, p: f64) -> Option<Self> {
+        if p.fract() == 0.0 && p.abs() <= i32::MAX as f64 {
+            return self.powi(p as i32)
+        }
+        Some(self.logm()?.strech(p).expm())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOL: f64 = 1e-9;
+
+    fn spd() -> Matrix {
+        Matrix::from([[4.0, 1.0, 0.5], [1.0, 3.0, 0.2], [0.5, 0.2, 2.0]])
+    }
+
+    #[test]
+    fn expm_of_zero_and_rotation_generators() {
+        crate::assert_matrix_close!(Matrix::zeros((3, 3)).expm(), Matrix::identity(3));
+        // small angles use the approximant directly, large ones also scale and square
+        for angle in [0.3, 1.2, 10.0] {
+            let generator = Matrix::from([[0.0, -angle], [angle, 0.0]]);
+            let (sin, cos) = f64::sin_cos(angle);
+            crate::assert_matrix_close!(generator.expm(), Matrix::from([[cos, -sin], [sin, cos]]), TOL, TOL)
+        };
+        let diagonal = Matrix::from([[1.0, 0.0], [0.0, -2.0]]);
+        crate::assert_matrix_close!(diagonal.expm(), Matrix::from([[1f64.exp(), 0.0], [0.0, (-2f64).exp()]]), TOL, TOL)
+    }
+
+    #[test]
+    fn sqrtm_squares_back() {
+        let a = spd();
+        let root = a.sqrtm().unwrap();
+        crate::assert_matrix_close!(root.clone() * root, a, TOL, TOL);
+        crate::assert_matrix_close!(Matrix::from([[4.0, 0.0], [0.0, 9.0]]).sqrtm().unwrap(), Matrix::from([[2.0, 0.0], [0.0, 3.0]]), TOL, TOL)
+    }
+
+    #[test]
+    fn logm_inverts_expm() {
+        let a = spd();
+        crate::assert_matrix_close!(a.logm().unwrap().expm(), a, TOL, TOL);
+        // not symmetric, with eigenvalues 2 and 3
+        let a = Matrix::from([[2.0, 1.0], [0.0, 3.0]]);
+        crate::assert_matrix_close!(a.logm().unwrap().expm(), a, TOL, TOL);
+        crate::assert_matrix_close!(Matrix::identity(2).logm().unwrap(), Matrix::zeros((2, 2)), TOL, TOL)
+    }
+
+    #[test]
+    fn integer_powers() {
+        let a = Matrix::from([[2.0, 1.0], [1.0, 1.0]]);
+        crate::assert_matrix_close!(a.powi(0).unwrap(), Matrix::identity(2));
+        crate::assert_matrix_close!(a.powi(3).unwrap(), a.clone() * a.clone() * a.clone());
+        let inverse = a.inverse().unwrap();
+        crate::assert_matrix_close!(a.powi(-2).unwrap(), inverse.clone() * inverse, TOL, TOL);
+        crate::assert_matrix_close!(a.powf(-2.0).unwrap(), a.powi(-2).unwrap())
+    }
+
+    #[test]
+    fn half_power_is_the_square_root() {
+        let a = spd();
+        crate::assert_matrix_close!(a.powf(0.5).unwrap(), a.sqrtm().unwrap(), TOL, TOL)
+    }
+
+    #[test]
+    fn none_cases() {
+        let singular = Matrix::from([[1.0, 2.0], [2.0, 4.0]]);
+        assert!(singular.powi(-1).is_none());
+        assert!(singular.powf(-2.0).is_none());
+        assert!(singular.powi(2).is_some());
+        // a negative real eigenvalue has no real principal root or logarithm
+        for a in [Matrix::from([[-1.0, 0.0], [0.0, 4.0]]), Matrix::from([[-2.0, 0.0], [0.0, 3.0]])] {
+            assert!(a.sqrtm().is_none());
+            assert!(a.logm().is_none());
+            assert!(a.powf(0.5).is_none())
+        }
+    }
+}
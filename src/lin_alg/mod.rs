@@ -1,9 +1,31 @@
 mod matrix;
 mod vector;
 mod determinant;
+mod print_options;
+mod approx;
+mod npy;
+mod text;
+mod parse;
+mod conv;
+mod transform;
+mod rref;
+mod explain;
+mod fraction;
+mod render;
+mod matrix_fn;
 
 pub use matrix::*;
 pub use vector::*;
 pub use determinant::*;
+pub use print_options::*;
+pub use approx::{DEFAULT_RTOL, DEFAULT_ATOL};
+pub use npy::*;
+pub use text::*;
+pub use conv::*;
+pub use transform::*;
+pub use rref::*;
+pub use explain::*;
+pub use fraction::*;
+pub use render::*;
 
 use super::func;
\ No newline at end of file
@@ -0,0 +1,463 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Read, Write};
+
+use super::{Matrix, Vector};
+
+const MAGIC: &[u8] = b"\x93NUMPY";
+
+#[derive(Debug)]
+pub enum NpyError {
+    Io(std::io::Error),
+    Zip(zip::result::ZipError),
+    Header(String),
+    Dtype(String),
+    Shape { expected: usize, found: Vec<usize> },
+    // the data doesn't hold exactly the elements the shape asks for
+    Length { shape: Vec<usize>, bytes: usize },
+    Missing(String)
+}
+
+impl std::fmt::Display for NpyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NpyError::Io(err) => write!(f, "io error: {}", err),
+            NpyError::Zip(err) => write!(f, "npz archive error: {}", err),
+            NpyError::Header(msg) => write!(f, "invalid npy header: {}", msg),
+            NpyError::Dtype(descr) => write!(f, "unsupported dtype {:?}, expected f4 or f8", descr),
+            NpyError::Shape { expected, found } => write!(f, "expected a {}-d array, found shape {:?}", expected, found),
+            NpyError::Length { shape, bytes } => write!(f, "shape {:?} doesn't match {} bytes of data", shape, bytes),
+            NpyError::Missing(name) => write!(f, "no array named {:?} in the archive", name)
+        }
+    }
+}
+
+impl std::error::Error for NpyError {}
+
+impl From<std::io::Error> for NpyError {
+    fn from(err: std::io::Error) -> Self {
+        NpyError::Io(err)
+    }
+}
+
+impl From<zip::result::ZipError> for NpyError {
+    fn from(err: zip::result::ZipError) -> Self {
+        NpyError::Zip(err)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NpyDtype {
+    F32,
+    F64
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NpyOrder {
+    C,
+    Fortran
+}
+
+// a decoded array, always kept in C order
+struct NpyArray {
+    shape: Vec<usize>,
+    data: Vec<f64>
+}
+
+// the text after `'key':` up to the next comma outside of brackets
+fn header_value<'a>(header: &'a str, key: &str) -> Result<&'a str, NpyError> {
+    let pattern = format!("'{}':", key);
+    let start = header.find(&pattern)
+        .ok_or_else(|| NpyError::Header(format!("missing key '{}'", key)))? + pattern.len();
+    let rest = &header[start..];
+    let mut depth = 0;
+    for (index, c) in rest.char_indices() {
+        match c {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth -= 1,
+            ',' | '}' if depth == 0 => return Ok(rest[..index].trim()),
+            _ => {}
+        }
+    };
+    Err(NpyError::Header(format!("unterminated value for '{}'", key)))
+}
+
+fn parse_header(header: &str) -> Result<(String, bool, Vec<usize>), NpyError> {
+    let descr = header_value(header, "descr")?.trim_matches(|c| c == '\'' || c == '"').to_string();
+    let fortran = match header_value(header, "fortran_order")? {
+        "True" => true,
+        "False" => false,
+        other => return Err(NpyError::Header(format!("bad fortran_order {:?}", other)))
+    };
+    let shape_text = header_value(header, "shape")?;
+    let shape = shape_text.trim_start_matches('(').trim_end_matches(')')
+        .split(',')
+        .map(|i| i.trim())
+        .filter(|i| !i.is_empty())
+        .map(|i| i.parse::<usize>().map_err(|_| NpyError::Header(format!("bad shape {:?}", shape_text))))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok((descr, fortran, shape))
+}
+
+fn decode(bytes: &[u8]) -> Result<NpyArray, NpyError> {
+    if bytes.len() < 10 || &bytes[..6] != MAGIC {
+        return Err(NpyError::Header("not an npy file".to_string()))
+    }
+    let (header_len, offset) = match bytes[6] {
+        1 => (u16::from_le_bytes([bytes[8], bytes[9]]) as usize, 10),
+        2 | 3 if bytes.len() >= 12 => (u32::from_le_bytes([bytes[8], bytes[9], bytes[10], bytes[11]]) as usize, 12),
+        version => return Err(NpyError::Header(format!("unsupported version {}", version)))
+    };
+    let header = bytes.get(offset..offset + header_len)
+        .ok_or_else(|| NpyError::Header("truncated header".to_string()))?;
+    let header = std::str::from_utf8(header).map_err(|_| NpyError::Header("header is not text".to_string()))?;
+    let (descr, fortran, shape) = parse_header(header)?;
+
+    let (little, size) = match descr.as_str() {
+        "<f8" | "=f8" | "f8" => (true, 8),
+        ">f8" => (false, 8),
+        "<f4" | "=f4" | "f4" => (true, 4),
+        ">f4" => (false, 4),
+        _ => return Err(NpyError::Dtype(descr))
+    };
+
+    // a hostile header can ask for more elements than fit in a usize
+    let length = shape.iter().try_fold(size, |acc: usize, i| acc.checked_mul(*i))
+        .ok_or_else(|| NpyError::Header(format!("shape {:?} is too large", shape)))?;
+    let body = &bytes[offset + header_len..];
+    if body.len() != length {
+        return Err(NpyError::Length { shape, bytes: body.len() })
+    }
+    let count = length / size;
+
+    let mut data = Vec::with_capacity(count);
+    for chunk in body.chunks_exact(size) {
+        let val = match (size, little) {
+            (8, true) => f64::from_le_bytes(chunk.try_into().unwrap()),
+            (8, false) => f64::from_be_bytes(chunk.try_into().unwrap()),
+            (_, true) => f32::from_le_bytes(chunk.try_into().unwrap()) as f64,
+            (_, false) => f32::from_be_bytes(chunk.try_into().unwrap()) as f64
+        };
+        data.push(val)
+    };
+
+    if fortran && shape.len() == 2 {
+        let (rows, cols) = (shape[0], shape[1]);
+        let mut buf = vec![0.0; count];
+        for r in 0..rows {
+            for c in 0..cols {
+                buf[r * cols + c] = data[c * rows + r]
+            }
+        };
+        data = buf
+    }
+    Ok(NpyArray { shape, data })
+}
+
+fn encode(shape: &[usize], data: &[f64], dtype: NpyDtype, order: NpyOrder) -> Vec<u8> {
+    let descr = match dtype {
+        NpyDtype::F32 => "<f4",
+        NpyDtype::F64 => "<f8"
+    };
+    let shape_text = match shape {
+        [n] => format!("({},)", n),
+        _ => format!("({})", shape.iter().map(|i| i.to_string()).collect::<Vec<_>>().join(", "))
+    };
+    let mut header = format!(
+        "{{'descr': '{}', 'fortran_order': {}, 'shape': {}, }}",
+        descr,
+        if order == NpyOrder::Fortran { "True" } else { "False" },
+        shape_text
+    );
+    // the magic, version and header length take 10 bytes, the whole preamble is padded to 64
+    while (10 + header.len() + 1) % 64 != 0 {
+        header.push(' ')
+    };
+    header.push('\n');
+
+    let mut out = Vec::new();
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&[1, 0]);
+    out.extend_from_slice(&(header.len() as u16).to_le_bytes());
+    out.extend_from_slice(header.as_bytes());
+
+    let mut push = |val: f64| match dtype {
+        NpyDtype::F32 => out.extend_from_slice(&(val as f32).to_le_bytes()),
+        NpyDtype::F64 => out.extend_from_slice(&val.to_le_bytes())
+    };
+    if order == NpyOrder::Fortran && shape.len() == 2 {
+        let (rows, cols) = (shape[0], shape[1]);
+        for c in 0..cols {
+            for r in 0..rows {
+                push(data[r * cols + c])
+            }
+        }
+    } else {
+        for val in data.iter() {
+            push(*val)
+        }
+    };
+    out
+}
+
+impl NpyArray {
+    fn into_matrix(self) -> Result<Matrix, NpyError> {
+        if self.shape.len() != 2 {
+            return Err(NpyError::Shape { expected: 2, found: self.shape })
+        }
+        let (rows, cols) = (self.shape[0], self.shape[1]);
+        if rows == 0 || cols == 0 {
+            return Ok(Matrix::new((cols, rows)))
+        }
+        Ok(Matrix::from_vec(self.data.chunks(cols).map(|i| i.to_vec()).collect()))
+    }
+
+    fn into_vector(self) -> Result<Vector, NpyError> {
+        if self.shape.len() != 1 {
+            return Err(NpyError::Shape { expected: 1, found: self.shape })
+        }
+        Ok(Vector::from_vec(self.data))
+    }
+}
+
+impl Matrix {
+    pub fn from_npy_bytes(bytes: &[u8]) -> Result<Self, NpyError> {
+        decode(bytes)?.into_matrix()
+    }
+
+    pub fn to_npy_bytes(&self, dtype: NpyDtype, order: NpyOrder) -> Vec<u8> {
+        let data = self.iter().flat_map(|i| i.iter().copied()).collect::<Vec<_>>();
+        encode(&[self.shape().1, self.shape().0], &data, dtype, order)
+    }
+
+    pub fn read_npy(path: &str) -> Result<Self, NpyError> {
+        Self::from_npy_bytes(&std::fs::read(path)?)
+    }
+
+    pub fn write_npy(&self, path: &str) -> Result<(), NpyError> {
+        self.write_npy_with(path, NpyDtype::F64, NpyOrder::C)
+    }
+
+    pub fn write_npy_with(&self, path: &str, dtype: NpyDtype, order: NpyOrder) -> Result<(), NpyError> {
+        Ok(std::fs::write(path, self.to_npy_bytes(dtype, order))?)
+    }
+}
+
+impl Vector {
+    pub fn from_npy_bytes(bytes: &[u8]) -> Result<Self, NpyError> {
+        decode(bytes)?.into_vector()
+    }
+
+    pub fn to_npy_bytes(&self, dtype: NpyDtype) -> Vec<u8> {
+        encode(&[self.len()], self.get_val(), dtype, NpyOrder::C)
+    }
+
+    pub fn read_npy(path: &str) -> Result<Self, NpyError> {
+        Self::from_npy_bytes(&std::fs::read(path)?)
+    }
+
+    pub fn write_npy(&self, path: &str) -> Result<(), NpyError> {
+        self.write_npy_with(path, NpyDtype::F64)
+    }
+
+    pub fn write_npy_with(&self, path: &str, dtype: NpyDtype) -> Result<(), NpyError> {
+        Ok(std::fs::write(path, self.to_npy_bytes(dtype))?)
+    }
+}
+
+// reads every member of an archive written by numpy.savez or numpy.savez_compressed
+pub struct NpzReader {
+    arrays: HashMap<String, Vec<u8>>
+}
+
+impl NpzReader {
+    pub fn open(path: &str) -> Result<Self, NpyError> {
+        let mut archive = zip::ZipArchive::new(File::open(path)?)?;
+        let mut arrays = HashMap::new();
+        for i in 0..archive.len() {
+            let mut file = archive.by_index(i)?;
+            let name = file.name().trim_end_matches(".npy").to_string();
+            let mut buf = Vec::new();
+            file.read_to_end(&mut buf)?;
+            arrays.insert(name, buf);
+        };
+        Ok(Self { arrays })
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        let mut names = self.arrays.keys().cloned().collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+
+    fn bytes(&self, name: &str) -> Result<&[u8], NpyError> {
+        self.arrays.get(name).map(|i| i.as_slice()).ok_or_else(|| NpyError::Missing(name.to_string()))
+    }
+
+    pub fn matrix(&self, name: &str) -> Result<Matrix, NpyError> {
+        Matrix::from_npy_bytes(self.bytes(name)?)
+    }
+
+    pub fn vector(&self, name: &str) -> Result<Vector, NpyError> {
+        Vector::from_npy_bytes(self.bytes(name)?)
+    }
+}
+
+pub struct NpzWriter {
+    zip: zip::ZipWriter<File>,
+    options: zip::write::FileOptions
+}
+
+impl NpzWriter {
+    pub fn create(path: &str) -> Result<Self, NpyError> {
+        Ok(Self {
+            zip: zip::ZipWriter::new(File::create(path)?),
+            options: zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Stored)
+        })
+    }
+
+    pub fn compressed(mut self) -> Self {
+        self.options = self.options.compression_method(zip::CompressionMethod::Deflated);
+        self
+    }
+
+    fn add(&mut self, name: &str, bytes: Vec<u8>) -> Result<(), NpyError> {
+        self.zip.start_file(format!("{}.npy", name), self.options)?;
+        Ok(self.zip.write_all(&bytes)?)
+    }
+
+    pub fn add_matrix(&mut self, name: &str, matrix: &Matrix) -> Result<(), NpyError> {
+        self.add(name, matrix.to_npy_bytes(NpyDtype::F64, NpyOrder::C))
+    }
+
+    pub fn add_vector(&mut self, name: &str, vector: &Vector) -> Result<(), NpyError> {
+        self.add(name, vector.to_npy_bytes(NpyDtype::F64))
+    }
+
+    pub fn finish(mut self) -> Result<(), NpyError> {
+        self.zip.finish()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with_header(descr: &str, fortran: bool, shape: &str, body: &[u8]) -> Vec<u8> {
+        let fortran = if fortran { "True" } else { "False" };
+        let header = format!("{{'descr': '{}', 'fortran_order': {}, 'shape': {}, }}\n", descr, fortran, shape);
+        let mut out = Vec::new();
+        out.extend_from_slice(MAGIC);
+        out.extend_from_slice(&[1, 0]);
+        out.extend_from_slice(&(header.len() as u16).to_le_bytes());
+        out.extend_from_slice(header.as_bytes());
+        out.extend_from_slice(body);
+        out
+    }
+
+    fn with_shape(shape: &str, body: &[u8]) -> Vec<u8> {
+        with_header("<f8", false, shape, body)
+    }
+
+    fn temp_path(name: &str) -> String {
+        let path = std::env::temp_dir().join(format!("nn_rust_{}_{}", std::process::id(), name));
+        path.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn round_trip() {
+        let matrix = Matrix::from([[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]);
+        for order in [NpyOrder::C, NpyOrder::Fortran] {
+            let out = Matrix::from_npy_bytes(&matrix.to_npy_bytes(NpyDtype::F64, order)).unwrap();
+            crate::assert_matrix_close!(out, matrix)
+        }
+    }
+
+    #[test]
+    fn overflowing_shape() {
+        let bytes = with_shape(&format!("({}, {})", usize::MAX, 2), &[0; 16]);
+        assert!(matches!(Matrix::from_npy_bytes(&bytes), Err(NpyError::Header(_))))
+    }
+
+    #[test]
+    fn wrong_length() {
+        let bytes = with_shape("(1, 2)", &[0; 8]);
+        assert!(matches!(Matrix::from_npy_bytes(&bytes), Err(NpyError::Length { .. })));
+        let bytes = with_shape("(1, 2)", &[0; 17]);
+        assert!(matches!(Matrix::from_npy_bytes(&bytes), Err(NpyError::Length { .. })))
+    }
+
+    #[test]
+    fn f4_and_big_endian_f8() {
+        let matrix = Matrix::from([[1.5, -2.0], [0.25, 8.0]]);
+        let out = Matrix::from_npy_bytes(&matrix.to_npy_bytes(NpyDtype::F32, NpyOrder::C)).unwrap();
+        crate::assert_matrix_close!(out, matrix);
+
+        let body = [1.5f64, -2.0, 0.25, 8.0].iter().flat_map(|i| i.to_be_bytes()).collect::<Vec<_>>();
+        let out = Matrix::from_npy_bytes(&with_header(">f8", false, "(2, 2)", &body)).unwrap();
+        crate::assert_matrix_close!(out, matrix)
+    }
+
+    #[test]
+    fn fortran_order() {
+        // column by column: (1, 4), (2, 5), (3, 6)
+        let body = [1.0f64, 4.0, 2.0, 5.0, 3.0, 6.0].iter().flat_map(|i| i.to_le_bytes()).collect::<Vec<_>>();
+        let out = Matrix::from_npy_bytes(&with_header("<f8", true, "(2, 3)", &body)).unwrap();
+        crate::assert_matrix_close!(out, Matrix::from([[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]));
+        let bytes = Matrix::from([[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]).to_npy_bytes(NpyDtype::F64, NpyOrder::Fortran);
+        assert!(bytes.ends_with(&body))
+    }
+
+    #[test]
+    fn unsupported_dtype_and_shape() {
+        let bytes = with_header("<i8", false, "(2,)", &[0; 16]);
+        assert!(matches!(Vector::from_npy_bytes(&bytes), Err(NpyError::Dtype(descr)) if descr == "<i8"));
+        let bytes = with_shape("(2,)", &[0; 16]);
+        assert!(matches!(Matrix::from_npy_bytes(&bytes), Err(NpyError::Shape { expected: 2, .. })));
+        assert!(matches!(Matrix::from_npy_bytes(b"not numpy"), Err(NpyError::Header(_))))
+    }
+
+    #[test]
+    fn npz_round_trip() {
+        let matrix = Matrix::from([[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]);
+        let vector = Vector::from([0.5, -1.5]);
+        for compressed in [false, true] {
+            let path = temp_path(&format!("arrays_{}.npz", compressed));
+            let mut writer = NpzWriter::create(&path).unwrap();
+            if compressed { writer = writer.compressed() }
+            writer.add_matrix("weights", &matrix).unwrap();
+            writer.add_vector("bias", &vector).unwrap();
+            writer.finish().unwrap();
+
+            let reader = NpzReader::open(&path).unwrap();
+            std::fs::remove_file(&path).unwrap();
+            assert_eq!(reader.names(), vec!["bias".to_string(), "weights".to_string()]);
+            crate::assert_matrix_close!(reader.matrix("weights").unwrap(), matrix);
+            crate::assert_vector_close!(reader.vector("bias").unwrap(), vector);
+            assert!(matches!(reader.matrix("missing"), Err(NpyError::Missing(_))))
+        }
+    }
+
+    #[test]
+    fn npy_file_round_trip() {
+        let path = temp_path("matrix.npy");
+        let matrix = Matrix::from([[1.0, 2.0], [3.0, 4.0]]);
+        matrix.write_npy(&path).unwrap();
+        let out = Matrix::read_npy(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        crate::assert_matrix_close!(out, matrix)
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let matrix = Matrix::from([[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]);
+        let text = serde_json::to_string(&matrix).unwrap();
+        crate::assert_matrix_close!(serde_json::from_str::<Matrix>(&text).unwrap(), matrix);
+        let vector = Vector::from([1.0, 2.0]);
+        assert_eq!(serde_json::to_string(&vector).unwrap(), "[1.0,2.0]");
+        // rows that don't match the shape are rejected
+        assert!(serde_json::from_str::<Matrix>(r#"{"vec": [[1.0, 2.0]], "shape": [3, 1]}"#).is_err())
+    }
+}
@@ -0,0 +1,227 @@
+use super::{Determinant, Matrix, ParseError, Vector};
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+    column: usize
+}
+
+impl Parser {
+    fn new(text: &str) -> Self {
+        Self { chars: text.chars().collect(), pos: 0, line: 1, column: 1 }
+    }
+
+    fn error(&self, message: &str) -> ParseError {
+        ParseError::new(self.line, self.column, message)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1
+        } else {
+            self.column += 1
+        };
+        Some(c)
+    }
+
+    // skip blanks, but stop at a newline when it separates MATLAB rows
+    fn skip_blank(&mut self, newline: bool) {
+        while let Some(c) = self.peek() {
+            if c.is_whitespace() && (newline || c != '\n') {
+                self.bump();
+            } else {
+                break
+            }
+        }
+    }
+
+    fn expect(&mut self, want: char) -> Result<(), ParseError> {
+        match self.peek() {
+            Some(c) if c == want => { self.bump(); Ok(()) },
+            Some(c) => Err(self.error(&format!("expected '{}', found '{}'", want, c))),
+            None => Err(self.error(&format!("expected '{}', found end of input", want)))
+        }
+    }
+
+    fn push_row(&self, rows: &mut Vec<Vec<f64>>, row: Vec<f64>) -> Result<(), ParseError> {
+        if let Some(first) = rows.first() {
+            if first.len() != row.len() {
+                return Err(self.error(&format!("expected {} values in the row, found {}", first.len(), row.len())))
+            }
+        }
+        rows.push(row);
+        Ok(())
+    }
+
+    fn number(&mut self) -> Result<f64, ParseError> {
+        let (line, column, start) = (self.line, self.column, self.pos);
+        while let Some(c) = self.peek() {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '+' || c == '-' {
+                self.bump();
+            } else {
+                break
+            }
+        };
+        let word = self.chars[start..self.pos].iter().collect::<String>();
+        if word.is_empty() {
+            return Err(match self.peek() {
+                Some(c) => self.error(&format!("expected a number, found '{}'", c)),
+                None => self.error("expected a number, found end of input")
+            })
+        }
+        word.parse::<f64>().map_err(|_| ParseError::new(line, column, &format!("invalid number {:?}", word)))
+    }
+
+    // `[[1, 2], [3, 4]]`, the outer '[' is already consumed
+    fn nested(&mut self) -> Result<Vec<Vec<f64>>, ParseError> {
+        let mut rows = Vec::new();
+        loop {
+            self.skip_blank(true);
+            self.expect('[')?;
+            let mut row = Vec::new();
+            self.skip_blank(true);
+            if self.peek() != Some(']') {
+                loop {
+                    self.skip_blank(true);
+                    row.push(self.number()?);
+                    self.skip_blank(true);
+                    if self.peek() == Some(',') { self.bump(); } else { break }
+                }
+            }
+            self.push_row(&mut rows, row)?;
+            self.expect(']')?;
+            self.skip_blank(true);
+            match self.peek() {
+                Some(',') => { self.bump(); },
+                _ => break
+            }
+        };
+        self.expect(']')?;
+        Ok(rows)
+    }
+
+    // `[1 2; 3 4]`, rows end at ';' or a newline, the opening bracket is already consumed
+    fn matlab(&mut self, close: char) -> Result<Vec<Vec<f64>>, ParseError> {
+        let mut rows = Vec::new();
+        let mut row = Vec::new();
+        loop {
+            self.skip_blank(false);
+            match self.peek() {
+                Some(c) if c == close => {
+                    self.bump();
+                    if !row.is_empty() { self.push_row(&mut rows, row)? }
+                    return Ok(rows)
+                },
+                Some(';') | Some('\n') => {
+                    if !row.is_empty() { self.push_row(&mut rows, std::mem::take(&mut row))? }
+                    self.bump();
+                },
+                Some(',') if !row.is_empty() => {
+                    self.bump();
+                    self.skip_blank(false);
+                    row.push(self.number()?)
+                },
+                _ => row.push(self.number()?)
+            }
+        }
+    }
+
+    fn rows(&mut self, allow_paren: bool) -> Result<Vec<Vec<f64>>, ParseError> {
+        self.skip_blank(true);
+        let rows = match self.peek() {
+            Some('[') => {
+                self.bump();
+                self.skip_blank(true);
+                if self.peek() == Some('[') { self.nested()? } else { self.matlab(']')? }
+            },
+            Some('(') if allow_paren => {
+                self.bump();
+                self.matlab(')')?
+            },
+            Some(c) => return Err(self.error(&format!("expected '[', found '{}'", c))),
+            None => return Err(self.error("expected '[', found end of input"))
+        };
+        self.skip_blank(true);
+        if let Some(c) = self.peek() {
+            return Err(self.error(&format!("unexpected '{}' after the closing bracket", c)))
+        }
+        Ok(rows)
+    }
+}
+
+impl std::str::FromStr for Matrix {
+    type Err = ParseError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rows = Parser::new(s).rows(false)?;
+        if rows.is_empty() {
+            return Ok(Matrix::new((0, 0)))
+        }
+        Ok(Matrix::from_vec(rows))
+    }
+}
+
+impl std::str::FromStr for Vector {
+    type Err = ParseError;
+    // a single row or a single column, `(1, 2, 3)` as printed by Display works too
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rows = Parser::new(s).rows(true)?;
+        if rows.len() <= 1 {
+            return Ok(Vector::from_vec(rows.into_iter().next().unwrap_or_default()))
+        }
+        if rows.iter().all(|i| i.len() == 1) {
+            return Ok(Vector::from_vec(rows.into_iter().map(|i| i[0]).collect()))
+        }
+        Err(ParseError::new(1, 1, "a vector must be a single row or column"))
+    }
+}
+
+impl std::str::FromStr for Determinant {
+    type Err = ParseError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rows = Parser::new(s).rows(false)?;
+        if rows.iter().any(|i| i.len() != rows.len()) {
+            return Err(ParseError::new(1, 1, "a determinant must be square"))
+        }
+        Ok(Determinant::from_vec(rows))
+    }
+}
+
+// matrix![1, 2; 3, 4], the rows are fixed-size arrays so a ragged row is a type error
+#[macro_export]
+macro_rules! matrix {
+    () => {
+        $crate::lin_alg::Matrix::new((0, 0))
+    };
+    ($($($x:expr),+ $(,)?);+ $(;)?) => {
+        $crate::lin_alg::Matrix::from([$([$($x as f64),+]),+])
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matrix_macro() {
+        let out = crate::matrix![1, 2, 3; 4.5, 5, 6];
+        assert_eq!(out.shape(), (3, 2));
+        crate::assert_matrix_close!(out, Matrix::from([[1.0, 2.0, 3.0], [4.5, 5.0, 6.0]]));
+        assert_eq!(crate::matrix![].shape(), (0, 0))
+    }
+
+    #[test]
+    fn parse_both_notations() {
+        let expected = Matrix::from([[1.0, 2.0], [3.0, 4.0]]);
+        crate::assert_matrix_close!("[1 2; 3 4]".parse::<Matrix>().unwrap(), expected);
+        crate::assert_matrix_close!("[[1, 2], [3, 4]]".parse::<Matrix>().unwrap(), expected);
+        assert!("[1 2; 3]".parse::<Matrix>().is_err())
+    }
+}
@@ -0,0 +1,171 @@
+use super::Matrix;
+
+#[derive(Clone, Copy, Debug)]
+pub struct PrintOptions {
+    pub precision: Option<usize>,
+    // summarise once the matrix holds more elements than this
+    pub threshold: usize,
+    // rows / columns kept at each end of a summarised dimension
+    pub edge_items: usize,
+    pub align: bool
+}
+
+impl Default for PrintOptions {
+    fn default() -> Self {
+        Self {
+            precision: None,
+            threshold: 1000,
+            edge_items: 3,
+            align: true
+        }
+    }
+}
+
+impl PrintOptions {
+    pub fn precision(mut self, precision: usize) -> Self {
+        self.precision = Some(precision);
+        self
+    }
+
+    pub fn threshold(mut self, threshold: usize) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    pub fn edge_items(mut self, edge_items: usize) -> Self {
+        self.edge_items = edge_items;
+        self
+    }
+
+    pub fn align(mut self, align: bool) -> Self {
+        self.align = align;
+        self
+    }
+
+    // the indexes to print along one dimension, `None` stands for the ellipsis
+    fn pick(&self, length: usize, summarise: bool) -> Vec<Option<usize>> {
+        if !summarise || length <= self.edge_items * 2 {
+            return (0..length).map(Some).collect()
+        }
+        let mut out: Vec<Option<usize>> = (0..self.edge_items).map(Some).collect();
+        out.push(None);
+        out.extend((length - self.edge_items..length).map(Some));
+        out
+    }
+
+    fn word(&self, val: f64) -> String {
+        match self.precision {
+            Some(precision) => format!("{:.*}", precision, val),
+            None => format!("{}", val)
+        }
+    }
+
+    // every row of the matrix turned into strings, with "..." standing in for skipped items
+    pub(crate) fn words(&self, matrix: &Matrix) -> Vec<Option<Vec<String>>> {
+        let (x, y) = matrix.shape();
+        let summarise = x * y > self.threshold;
+        let cols = self.pick(x, summarise);
+
+        let mut words_buf = Vec::new();
+        for row in self.pick(y, summarise) {
+            words_buf.push(row.map(|row| {
+                cols.iter().map(|col| match col {
+                    Some(col) => self.word(matrix.get(*col, row)),
+                    None => "...".to_string()
+                }).collect::<Vec<_>>()
+            }))
+        };
+
+        if self.align {
+            let mut max_length = vec![0; cols.len()];
+            for row in words_buf.iter().flatten() {
+                for (index, word) in row.iter().enumerate() {
+                    max_length[index] = max_length[index].max(word.len())
+                }
+            };
+            for row in words_buf.iter_mut().flatten() {
+                for (index, word) in row.iter_mut().enumerate() {
+                    *word = format!("{:>width$}", word, width = max_length[index])
+                }
+            }
+        }
+        words_buf
+    }
+}
+
+pub struct MatrixDisplay<'a> {
+    pub(crate) matrix: &'a Matrix,
+    pub(crate) options: PrintOptions
+}
+
+impl std::fmt::Display for MatrixDisplay<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let rows = self.options.words(self.matrix).into_iter().map(|row| match row {
+            Some(row) => format!("({})", row.join(", ")),
+            None => "...".to_string()
+        }).collect::<Vec<_>>();
+        write!(f, "Matrix[\n    {}\n]", rows.join("\n    "))
+    }
+}
+
+// right-align every column like Determinant's Display, then wrap each row in `open` and `close`
+pub(crate) fn aligned_rows(words: &[Vec<String>], open: &str, close: &str) -> Vec<String> {
+    let mut max_length = Vec::new();
+    for row in words.iter() {
+        for (index, word) in row.iter().enumerate() {
+            if index == max_length.len() { max_length.push(0) }
+            max_length[index] = max_length[index].max(word.chars().count())
+        }
+    };
+    words.iter().map(|row| {
+        let row = row.iter().enumerate()
+            .map(|(index, word)| format!("{:>width$}", word, width = max_length[index]))
+            .collect::<Vec<_>>();
+        format!("{}{}{}", open, row.join(" "), close)
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counting(cols: usize, rows: usize) -> Matrix {
+        Matrix::from_vec((0..rows).map(|y| (0..cols).map(|x| (y * cols + x) as f64).collect()).collect())
+    }
+
+    #[test]
+    fn small_matrix_is_printed_whole() {
+        assert_eq!(format!("{}", Matrix::from([[1.0, 2.0], [3.0, 4.0]])), "Matrix[\n    (1, 2)\n    (3, 4)\n]")
+    }
+
+    #[test]
+    fn summarised_past_the_threshold() {
+        let options = PrintOptions::default().threshold(20).edge_items(1);
+        let out = format!("{}", counting(5, 5).display_with(options));
+        assert_eq!(out, "Matrix[\n    ( 0, ...,  4)\n    ...\n    (20, ..., 24)\n]");
+        // at the threshold nothing is skipped
+        let out = format!("{}", counting(5, 4).display_with(options));
+        assert!(!out.contains("..."))
+    }
+
+    #[test]
+    fn precision() {
+        let matrix = Matrix::from([[1.0, 2.5], [-0.125, 10.0]]);
+        assert_eq!(format!("{:.3}", matrix), "Matrix[\n    ( 1.000,  2.500)\n    (-0.125, 10.000)\n]")
+    }
+
+    #[test]
+    fn columns_are_aligned() {
+        let matrix = Matrix::from([[1.0, 200.0], [30.0, 4.0]]);
+        assert_eq!(format!("{}", matrix), "Matrix[\n    ( 1, 200)\n    (30,   4)\n]");
+        let out = format!("{}", matrix.display_with(PrintOptions::default().align(false)));
+        assert_eq!(out, "Matrix[\n    (1, 200)\n    (30, 4)\n]")
+    }
+
+    #[test]
+    fn debug() {
+        let matrix = Matrix::from([[1.0, 200.0], [30.0, 4.0]]);
+        assert_eq!(format!("{:?}", matrix), "Matrix { shape: (2, 2), val: [[1, 200], [30, 4]] }");
+        assert_eq!(format!("{:.1?}", matrix), "Matrix { shape: (2, 2), val: [[1.0, 200.0], [30.0, 4.0]] }")
+    }
+}
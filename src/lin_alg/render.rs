@@ -0,0 +1,182 @@
+use super::{aligned_rows, DeterNum, Determinant, Fraction, Matrix, Vector};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum NumberFormat {
+    // the shortest form that reads back the same, like `{}`
+    #[default]
+    Plain,
+    // fixed number of decimals, like `{:.3}`
+    Fixed(usize),
+    // scientific with the given number of decimals, like `{:.3e}`
+    Sci(usize)
+}
+
+impl NumberFormat {
+    pub fn text(&self, val: f64) -> String {
+        match *self {
+            NumberFormat::Plain => format!("{}", val),
+            NumberFormat::Fixed(precision) => format!("{:.*}", precision, val),
+            NumberFormat::Sci(precision) => format!("{:.*e}", precision, val)
+        }
+    }
+
+    pub fn latex(&self, val: f64) -> String {
+        let text = self.text(val);
+        match text.split_once('e') {
+            Some((mantissa, exp)) if *self != NumberFormat::Plain => format!("{} \\times 10^{{{}}}", mantissa, exp),
+            _ => text
+        }
+    }
+}
+
+fn latex_frac(frac: Fraction) -> String {
+    match (frac.num(), frac.den()) {
+        (num, 1) => format!("{}", num),
+        (num, den) if num < 0 => format!("-\\frac{{{}}}{{{}}}", -num, den),
+        (num, den) => format!("\\frac{{{}}}{{{}}}", num, den)
+    }
+}
+
+fn latex_env(env: &str, rows: &[Vec<String>]) -> String {
+    let body = rows.iter().map(|i| i.join(" & ")).collect::<Vec<_>>().join(" \\\\\n    ");
+    format!("\\begin{{{}}}\n    {}\n\\end{{{}}}", env, body, env)
+}
+
+fn latex_num(num: &DeterNum, format: NumberFormat) -> String {
+    match num {
+        DeterNum::Float(val) => format.latex(*val),
+        DeterNum::Frac(frac) => latex_frac(*frac),
+        DeterNum::Vec(vec) => format!(
+            "\\begin{{pmatrix}} {} \\end{{pmatrix}}",
+            vec.iter().map(|i| format.latex(*i)).collect::<Vec<_>>().join(" & ")
+        )
+    }
+}
+
+// tall brackets out of the Unicode bracket pieces, plain ones for a single line
+fn boxed(words: &[Vec<String>], left: [&str; 4], right: [&str; 4]) -> String {
+    let rows = aligned_rows(words, "", "");
+    let n = rows.len();
+    rows.iter().enumerate().map(|(index, row)| {
+        let piece = if n == 1 { 3 } else if index == 0 { 0 } else if index == n - 1 { 2 } else { 1 };
+        format!("{} {} {}", left[piece], row, right[piece])
+    }).collect::<Vec<_>>().join("\n")
+}
+
+const SQUARE_L: [&str; 4] = ["⎡", "⎢", "⎣", "["];
+const SQUARE_R: [&str; 4] = ["⎤", "⎥", "⎦", "]"];
+const ROUND_L: [&str; 4] = ["⎛", "⎜", "⎝", "("];
+const ROUND_R: [&str; 4] = ["⎞", "⎟", "⎠", ")"];
+const BAR: [&str; 4] = ["│", "│", "│", "│"];
+
+impl Matrix {
+    pub fn to_latex(&self) -> String {
+        self.to_latex_with(NumberFormat::Plain)
+    }
+
+    pub fn to_latex_with(&self, format: NumberFormat) -> String {
+        let rows = self.iter().map(|i| i.iter().map(|j| format.latex(*j)).collect()).collect::<Vec<_>>();
+        latex_env("bmatrix", &rows)
+    }
+
+    pub fn to_unicode(&self) -> String {
+        self.to_unicode_with(NumberFormat::Plain)
+    }
+
+    pub fn to_unicode_with(&self, format: NumberFormat) -> String {
+        let rows = self.iter().map(|i| i.iter().map(|j| format.text(*j)).collect()).collect::<Vec<_>>();
+        boxed(&rows, SQUARE_L, SQUARE_R)
+    }
+}
+
+// vectors are written as columns
+impl Vector {
+    pub fn to_latex(&self) -> String {
+        self.to_latex_with(NumberFormat::Plain)
+    }
+
+    pub fn to_latex_with(&self, format: NumberFormat) -> String {
+        let rows = self.iter().map(|i| vec![format.latex(*i)]).collect::<Vec<_>>();
+        latex_env("pmatrix", &rows)
+    }
+
+    pub fn to_unicode(&self) -> String {
+        self.to_unicode_with(NumberFormat::Plain)
+    }
+
+    pub fn to_unicode_with(&self, format: NumberFormat) -> String {
+        let rows = self.iter().map(|i| vec![format.text(*i)]).collect::<Vec<_>>();
+        boxed(&rows, ROUND_L, ROUND_R)
+    }
+}
+
+impl Determinant {
+    fn words(&self, each: &dyn Fn(&DeterNum) -> String) -> Vec<Vec<String>> {
+        (0..self.len()).map(|y| (0..self.len()).map(|x| each(self.get(x, y))).collect()).collect()
+    }
+
+    pub fn to_latex(&self) -> String {
+        self.to_latex_with(NumberFormat::Plain)
+    }
+
+    pub fn to_latex_with(&self, format: NumberFormat) -> String {
+        latex_env("vmatrix", &self.words(&|i| latex_num(i, format)))
+    }
+
+    pub fn to_unicode(&self) -> String {
+        self.to_unicode_with(NumberFormat::Plain)
+    }
+
+    pub fn to_unicode_with(&self, format: NumberFormat) -> String {
+        let words = self.words(&|i| match i {
+            DeterNum::Float(val) => format.text(*val),
+            DeterNum::Frac(frac) => format!("{}", frac),
+            DeterNum::Vec(vec) => format!("({})", vec.iter().map(|j| format.text(*j)).collect::<Vec<_>>().join(", "))
+        });
+        boxed(&words, BAR, BAR)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn one_by_one() {
+        let matrix = Matrix::from([[5.0]]);
+        assert_eq!(matrix.to_latex(), "\\begin{bmatrix}\n    5\n\\end{bmatrix}");
+        assert_eq!(matrix.to_unicode(), "[ 5 ]");
+        assert_eq!(Determinant::from([[-1.5]]).to_unicode(), "│ -1.5 │");
+        assert_eq!(Vector::from([2.0]).to_unicode(), "( 2 )")
+    }
+
+    #[test]
+    fn two_by_three() {
+        let matrix = Matrix::from([[1.0, -2.5, 3.0], [10.0, 0.0, 6.0]]);
+        assert_eq!(matrix.to_latex(), "\\begin{bmatrix}\n    1 & -2.5 & 3 \\\\\n    10 & 0 & 6\n\\end{bmatrix}");
+        assert_eq!(matrix.to_unicode(), "⎡  1 -2.5 3 ⎤\n⎣ 10    0 6 ⎦");
+        assert_eq!(
+            matrix.to_latex_with(NumberFormat::Fixed(1)),
+            "\\begin{bmatrix}\n    1.0 & -2.5 & 3.0 \\\\\n    10.0 & 0.0 & 6.0\n\\end{bmatrix}"
+        );
+        assert_eq!(Matrix::from([[1500.0]]).to_latex_with(NumberFormat::Sci(1)), "\\begin{bmatrix}\n    1.5 \\times 10^{3}\n\\end{bmatrix}");
+        assert_eq!(Vector::from([1.0, 2.0, 3.0]).to_unicode(), "⎛ 1 ⎞\n⎜ 2 ⎟\n⎝ 3 ⎠")
+    }
+
+    #[test]
+    fn determinant() {
+        let deter = Determinant::from([[1.0, 2.0, 3.0], [4.0, 5.0, 6.0], [7.0, 8.0, 10.0]]);
+        assert_eq!(deter.to_latex(), "\\begin{vmatrix}\n    1 & 2 & 3 \\\\\n    4 & 5 & 6 \\\\\n    7 & 8 & 10\n\\end{vmatrix}");
+        assert_eq!(deter.to_unicode(), "│ 1 2  3 │\n│ 4 5  6 │\n│ 7 8 10 │")
+    }
+
+    #[test]
+    fn fraction_entries() {
+        let deter = Determinant::from_frac(vec![
+            vec![Fraction::new(1, 2), Fraction::new(-3, 4)],
+            vec![Fraction::from_int(2), Fraction::new(2, 6)]
+        ]);
+        assert_eq!(deter.to_latex(), "\\begin{vmatrix}\n    \\frac{1}{2} & -\\frac{3}{4} \\\\\n    2 & \\frac{1}{3}\n\\end{vmatrix}");
+        assert_eq!(deter.to_unicode(), "│ 1/2 -3/4 │\n│   2  1/3 │")
+    }
+}
@@ -0,0 +1,216 @@
+use super::{aligned_rows, Matrix, Vector};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RowOp {
+    Swap(usize, usize),
+    // row = factor * row
+    Scale(usize, f64),
+    // target = target + factor * source
+    AddMultiple { target: usize, source: usize, factor: f64 }
+}
+
+impl std::fmt::Display for RowOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let num = |val: f64| match f.precision() {
+            Some(precision) => format!("{:.*}", precision, val),
+            None => format!("{}", val)
+        };
+        // rows are printed 1-based, the way they are written by hand
+        match *self {
+            RowOp::Swap(a, b) => write!(f, "R{} <-> R{}", a + 1, b + 1),
+            RowOp::Scale(row, factor) => write!(f, "R{} <- {} * R{}", row + 1, num(factor), row + 1),
+            RowOp::AddMultiple { target, source, factor } if factor < 0.0 =>
+                write!(f, "R{} <- R{} - {} * R{}", target + 1, target + 1, num(-factor), source + 1),
+            RowOp::AddMultiple { target, source, factor } =>
+                write!(f, "R{} <- R{} + {} * R{}", target + 1, target + 1, num(factor), source + 1)
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct RrefStep {
+    pub op: RowOp,
+    // the matrix right after the operation
+    pub matrix: Matrix
+}
+
+#[derive(Clone, Debug)]
+pub struct Rref {
+    pub matrix: Matrix,
+    pub pivots: Vec<usize>,
+    pub rank: usize,
+    pub null_space: Vec<Vector>,
+    pub steps: Vec<RrefStep>
+}
+
+fn render(matrix: &Matrix, f: &std::fmt::Formatter<'_>) -> String {
+    let words = matrix.iter().map(|row| row.iter().map(|val| match f.precision() {
+        Some(precision) => format!("{:.*}", precision, val),
+        None => format!("{}", val)
+    }).collect::<Vec<_>>()).collect::<Vec<_>>();
+    aligned_rows(&words, "| ", " |").join("\n")
+}
+
+impl std::fmt::Display for Rref {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for step in self.steps.iter() {
+            let op = match f.precision() {
+                Some(precision) => format!("{:.*}", precision, step.op),
+                None => format!("{}", step.op)
+            };
+            writeln!(f, "{}\n{}\n", op, render(&step.matrix, f))?
+        };
+        write!(f, "{}\npivots: {:?}, rank: {}", render(&self.matrix, f), self.pivots, self.rank)
+    }
+}
+
+impl Matrix {
+    pub fn rref(&self) -> Rref {
+        self.row_reduce(false)
+    }
+
+    // the same as rref, with every elementary row operation recorded in `steps`
+    pub fn rref_with_steps(&self) -> Rref {
+        self.row_reduce(true)
+    }
+
+    fn row_reduce(&self, trace: bool) -> Rref {
+        let (cols, rows) = self.shape();
+        let mut val = self.get_val();
+        let scale = val.iter().flatten().fold(1.0_f64, |max, i| max.max(i.abs()));
+        let tol = scale * 1e-12 * cols.max(rows) as f64;
+
+        let mut steps = Vec::new();
+        let mut record = |op: RowOp, val: &mut Vec<Vec<f64>>| {
+            match op {
+                RowOp::Swap(a, b) => val.swap(a, b),
+                RowOp::Scale(row, factor) => {
+                    for i in val[row].iter_mut() {
+                        *i *= factor
+                    }
+                },
+                RowOp::AddMultiple { target, source, factor } => {
+                    let source = val[source].clone();
+                    for (i, j) in val[target].iter_mut().zip(source.iter()) {
+                        *i += factor * j
+                    }
+                }
+            };
+            // clear the rounding noise so exact zeros show up as zeros
+            for i in val.iter_mut().flatten() {
+                if i.abs() < tol { *i = 0.0 }
+            };
+            if trace {
+                steps.push(RrefStep { op, matrix: Matrix::from_vec(val.clone()) })
+            }
+        };
+
+        let mut pivots = Vec::new();
+        let mut row = 0;
+        for col in 0..cols {
+            if row == rows { break }
+            // the first usable entry, the way it is done by hand
+            let pivot = match (row..rows).find(|i| val[*i][col].abs() > tol) {
+                Some(pivot) => pivot,
+                None => continue
+            };
+            if pivot != row {
+                record(RowOp::Swap(row, pivot), &mut val)
+            }
+            if val[row][col] != 1.0 {
+                record(RowOp::Scale(row, 1.0 / val[row][col]), &mut val)
+            }
+            for i in 0..rows {
+                if i != row && val[i][col] != 0.0 {
+                    record(RowOp::AddMultiple { target: i, source: row, factor: -val[i][col] }, &mut val)
+                }
+            };
+            pivots.push(col);
+            row += 1
+        };
+
+        let mut null_space = Vec::new();
+        for free in (0..cols).filter(|i| !pivots.contains(i)) {
+            let mut buf = vec![0.0; cols];
+            buf[free] = 1.0;
+            for (i, pivot) in pivots.iter().enumerate() {
+                if val[i][free] != 0.0 {
+                    buf[*pivot] = -val[i][free]
+                }
+            };
+            null_space.push(Vector::from_vec(buf))
+        };
+
+        Rref {
+            matrix: if rows == 0 { Matrix::zeros((cols, 0)) } else { Matrix::from_vec(val) },
+            rank: pivots.len(),
+            pivots,
+            null_space,
+            steps
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A n, by hand so it does not depend on the matrix-vector product
+    fn apply(a: &Matrix, n: &Vector) -> Vector {
+        let (cols, rows) = a.shape();
+        Vector::from_vec((0..rows).map(|y| (0..cols).map(|x| a.get(x, y) * n.get_val()[x]).sum()).collect())
+    }
+
+    #[test]
+    fn rank_deficient() {
+        let a = Matrix::from([[1.0, 2.0, 3.0], [2.0, 4.0, 6.0], [1.0, 1.0, 1.0]]);
+        let out = a.rref();
+        crate::assert_matrix_close!(out.matrix, Matrix::from([[1.0, 0.0, -1.0], [0.0, 1.0, 2.0], [0.0, 0.0, 0.0]]));
+        assert_eq!(out.pivots, vec![0, 1]);
+        assert_eq!(out.rank, 2);
+        assert_eq!(out.null_space.len(), 1);
+        crate::assert_vector_close!(out.null_space[0], Vector::from([1.0, -2.0, 1.0]));
+        crate::assert_vector_close!(apply(&a, &out.null_space[0]), Vector::from([0.0, 0.0, 0.0]));
+        assert!(out.steps.is_empty())
+    }
+
+    #[test]
+    fn null_space_of_a_wide_matrix() {
+        let a = Matrix::from([[2.0, 4.0, 1.0, 10.0], [1.0, 2.0, 1.0, 7.0], [3.0, 6.0, 0.0, 9.0]]);
+        let out = a.rref();
+        crate::assert_matrix_close!(out.matrix, Matrix::from([[1.0, 2.0, 0.0, 3.0], [0.0, 0.0, 1.0, 4.0], [0.0, 0.0, 0.0, 0.0]]));
+        assert_eq!((out.pivots.clone(), out.rank), (vec![0, 2], 2));
+        // one basis vector for each free column, rank + nullity = columns
+        assert_eq!(out.null_space.len(), 2);
+        crate::assert_vector_close!(out.null_space[0], Vector::from([-2.0, 1.0, 0.0, 0.0]));
+        crate::assert_vector_close!(out.null_space[1], Vector::from([-3.0, 0.0, -4.0, 1.0]));
+        for n in out.null_space.iter() {
+            crate::assert_vector_close!(apply(&a, n), Vector::from([0.0, 0.0, 0.0]), 1e-9, 1e-9)
+        };
+        // full rank has an empty null space
+        let out = Matrix::from([[2.0, 1.0], [1.0, 3.0]]).rref();
+        assert_eq!((out.pivots, out.rank, out.null_space.len()), (vec![0, 1], 2, 0));
+        crate::assert_matrix_close!(out.matrix, Matrix::identity(2))
+    }
+
+    #[test]
+    fn row_operation_trace() {
+        let a = Matrix::from([[1.0, 2.0, 3.0], [2.0, 4.0, 6.0], [1.0, 1.0, 1.0]]);
+        let out = a.rref_with_steps();
+        let ops = out.steps.iter().map(|i| i.op).collect::<Vec<_>>();
+        assert_eq!(ops, vec![
+            RowOp::AddMultiple { target: 1, source: 0, factor: -2.0 },
+            RowOp::AddMultiple { target: 2, source: 0, factor: -1.0 },
+            RowOp::Swap(1, 2),
+            RowOp::Scale(1, -1.0),
+            RowOp::AddMultiple { target: 0, source: 1, factor: -2.0 }
+        ]);
+        let text = ops.iter().map(|i| i.to_string()).collect::<Vec<_>>();
+        assert_eq!(text, ["R2 <- R2 - 2 * R1", "R3 <- R3 - 1 * R1", "R2 <-> R3", "R2 <- -1 * R2", "R1 <- R1 - 2 * R2"]);
+        crate::assert_matrix_close!(out.steps[2].matrix, Matrix::from([[1.0, 2.0, 3.0], [0.0, -1.0, -2.0], [0.0, 0.0, 0.0]]));
+        // the last step leaves the reduced matrix, and tracing does not change the result
+        crate::assert_matrix_close!(out.steps[4].matrix, out.matrix);
+        crate::assert_matrix_close!(out.matrix, a.rref().matrix);
+        assert_eq!(format!("{:.2}", RowOp::Scale(0, 1.0 / 3.0)), "R1 <- 0.33 * R1")
+    }
+}
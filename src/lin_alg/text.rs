@@ -0,0 +1,391 @@
+use std::fmt::Write;
+
+use super::Matrix;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String
+}
+
+impl ParseError {
+    pub fn new(line: usize, column: usize, message: &str) -> Self {
+        Self { line, column, message: message.to_string() }
+    }
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} at line {}, column {}", self.message, self.line, self.column)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[derive(Debug)]
+pub enum TextError {
+    Io(std::io::Error),
+    Parse(ParseError),
+    Unsupported(String)
+}
+
+impl std::fmt::Display for TextError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TextError::Io(err) => write!(f, "io error: {}", err),
+            TextError::Parse(err) => write!(f, "parse error: {}", err),
+            TextError::Unsupported(msg) => write!(f, "unsupported: {}", msg)
+        }
+    }
+}
+
+impl std::error::Error for TextError {}
+
+impl From<std::io::Error> for TextError {
+    fn from(err: std::io::Error) -> Self {
+        TextError::Io(err)
+    }
+}
+
+impl From<ParseError> for TextError {
+    fn from(err: ParseError) -> Self {
+        TextError::Parse(err)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MtxFormat {
+    Coordinate,
+    Array
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MtxSymmetry {
+    General,
+    Symmetric
+}
+
+// the non-empty, non-comment lines of a file with their 1-based line numbers
+fn content_lines<'a>(text: &'a str, comment: &'a str) -> impl Iterator<Item = (usize, &'a str)> {
+    text.lines().enumerate()
+        .map(|(index, line)| (index + 1, line))
+        .filter(move |(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with(comment))
+}
+
+// split a line on the delimiter, keeping the 1-based column each field starts at
+fn fields(line: &str, delimiter: Option<char>) -> Vec<(usize, &str)> {
+    let mut out = Vec::new();
+    let mut start = None;
+    for (index, c) in line.char_indices() {
+        let split = match delimiter {
+            Some(delimiter) => c == delimiter,
+            None => c.is_whitespace()
+        };
+        if split {
+            if let Some(begin) = start.take() {
+                out.push((begin, &line[begin..index]))
+            } else if delimiter.is_some() {
+                out.push((index, ""))
+            }
+        } else if start.is_none() {
+            start = Some(index)
+        }
+    };
+    match start {
+        Some(begin) => out.push((begin, &line[begin..])),
+        None if delimiter.is_some() => out.push((line.len(), "")),
+        None => {}
+    };
+    out.into_iter().map(|(index, field)| {
+        let trimmed = field.trim_start();
+        (index + field.len() - trimmed.len() + 1, trimmed.trim_end())
+    }).collect()
+}
+
+fn parse_num<T: std::str::FromStr>(line: usize, column: usize, field: &str) -> Result<T, ParseError> {
+    field.parse::<T>().map_err(|_| ParseError::new(line, column, &format!("invalid number {:?}", field)))
+}
+
+impl Matrix {
+    pub fn is_symmetric(&self) -> bool {
+        if self.shape().0 != self.shape().1 { return false }
+        for i in 0..self.shape().0 {
+            for j in 0..i {
+                if self.get(i, j) != self.get(j, i) { return false }
+            }
+        };
+        true
+    }
+
+    // rows are separated by lines, values by commas if the data has any and by whitespace otherwise,
+    // comment lines don't count so a `# a, b` header can sit above whitespace-separated data
+    pub fn from_txt_str(text: &str) -> Result<Self, ParseError> {
+        let lines = content_lines(text, "#").collect::<Vec<_>>();
+        let delimiter = if lines.iter().any(|(_, line)| line.contains(',')) { Some(',') } else { None };
+        let mut buf: Vec<Vec<f64>> = Vec::new();
+        for (line_no, line) in lines {
+            let mut row = Vec::new();
+            for (column, field) in fields(line, delimiter) {
+                row.push(parse_num(line_no, column, field)?)
+            };
+            if let Some(first) = buf.first() {
+                if first.len() != row.len() {
+                    return Err(ParseError::new(line_no, 1, &format!("expected {} values, found {}", first.len(), row.len())))
+                }
+            }
+            buf.push(row)
+        };
+        if buf.is_empty() {
+            return Ok(Matrix::new((0, 0)))
+        }
+        Ok(Matrix::from_vec(buf))
+    }
+
+    pub fn to_txt_string(&self, delimiter: char) -> String {
+        let mut out = String::new();
+        for row in self.iter() {
+            let words = row.iter().map(|i| i.to_string()).collect::<Vec<_>>();
+            let sep = if delimiter.is_whitespace() { delimiter.to_string() } else { format!("{} ", delimiter) };
+            out.push_str(&words.join(&sep));
+            out.push('\n')
+        };
+        out
+    }
+
+    pub fn read_txt(path: &str) -> Result<Self, TextError> {
+        Ok(Self::from_txt_str(&std::fs::read_to_string(path)?)?)
+    }
+
+    pub fn write_txt(&self, path: &str, delimiter: char) -> Result<(), TextError> {
+        Ok(std::fs::write(path, self.to_txt_string(delimiter))?)
+    }
+
+    pub fn from_mtx_str(text: &str) -> Result<Self, TextError> {
+        let banner = text.lines().next().unwrap_or("");
+        let words = banner.split_whitespace().map(|i| i.to_lowercase()).collect::<Vec<_>>();
+        if words.len() != 5 || words[0] != "%%matrixmarket" || words[1] != "matrix" {
+            return Err(ParseError::new(1, 1, "missing %%MatrixMarket matrix banner").into())
+        }
+        let format = match words[2].as_str() {
+            "coordinate" => MtxFormat::Coordinate,
+            "array" => MtxFormat::Array,
+            other => return Err(TextError::Unsupported(format!("format {}", other)))
+        };
+        let pattern = match words[3].as_str() {
+            "real" | "integer" | "double" => false,
+            "pattern" if format == MtxFormat::Coordinate => true,
+            other => return Err(TextError::Unsupported(format!("field {}", other)))
+        };
+        let symmetric = match words[4].as_str() {
+            "general" => false,
+            "symmetric" => true,
+            other => return Err(TextError::Unsupported(format!("symmetry {}", other)))
+        };
+
+        let mut lines = content_lines(text, "%");
+        let (size_line, size) = lines.next().ok_or_else(|| ParseError::new(2, 1, "missing size line"))?;
+        let size = fields(size, None);
+        let expected = if format == MtxFormat::Coordinate { 3 } else { 2 };
+        if size.len() != expected {
+            return Err(ParseError::new(size_line, 1, &format!("expected {} sizes, found {}", expected, size.len())).into())
+        }
+        let rows: usize = parse_num(size_line, size[0].0, size[0].1)?;
+        let cols: usize = parse_num(size_line, size[1].0, size[1].1)?;
+        if symmetric && rows != cols {
+            return Err(ParseError::new(size_line, 1, "a symmetric matrix must be square").into())
+        }
+        let mut out = Matrix::zeros((cols, rows));
+
+        match format {
+            MtxFormat::Coordinate => {
+                let entries: usize = parse_num(size_line, size[2].0, size[2].1)?;
+                let mut count = 0;
+                for (line_no, line) in lines {
+                    let entry = fields(line, None);
+                    let want = if pattern { 2 } else { 3 };
+                    if entry.len() != want {
+                        return Err(ParseError::new(line_no, 1, &format!("expected {} fields, found {}", want, entry.len())).into())
+                    }
+                    let i: usize = parse_num(line_no, entry[0].0, entry[0].1)?;
+                    let j: usize = parse_num(line_no, entry[1].0, entry[1].1)?;
+                    if i == 0 || j == 0 || i > rows || j > cols {
+                        return Err(ParseError::new(line_no, entry[0].0, &format!("index ({}, {}) out of range", i, j)).into())
+                    }
+                    let val = if pattern { 1.0 } else { parse_num(line_no, entry[2].0, entry[2].1)? };
+                    out.change_place((j - 1, i - 1), val);
+                    if symmetric {
+                        out.change_place((i - 1, j - 1), val)
+                    }
+                    count += 1
+                };
+                if count != entries {
+                    return Err(ParseError::new(size_line, size[2].0, &format!("expected {} entries, found {}", entries, count)).into())
+                }
+            },
+            MtxFormat::Array => {
+                // column-major, only the lower triangle for symmetric matrices
+                let mut places = Vec::new();
+                for j in 0..cols {
+                    for i in (if symmetric { j } else { 0 })..rows {
+                        places.push((i, j))
+                    }
+                };
+                let mut places = places.into_iter();
+                for (line_no, line) in lines {
+                    for (column, field) in fields(line, None) {
+                        let (i, j) = places.next()
+                            .ok_or_else(|| ParseError::new(line_no, column, "too many values"))?;
+                        let val = parse_num(line_no, column, field)?;
+                        out.change_place((j, i), val);
+                        if symmetric {
+                            out.change_place((i, j), val)
+                        }
+                    }
+                };
+                if places.next().is_some() {
+                    return Err(ParseError::new(text.lines().count(), 1, "not enough values").into())
+                }
+            }
+        };
+        Ok(out)
+    }
+
+    pub fn to_mtx_string(&self, format: MtxFormat, symmetry: MtxSymmetry) -> Result<String, TextError> {
+        let symmetric = symmetry == MtxSymmetry::Symmetric;
+        if symmetric && !self.is_symmetric() {
+            return Err(TextError::Unsupported("the matrix is not symmetric".to_string()))
+        }
+        let (cols, rows) = self.shape();
+        let mut out = String::new();
+        let _ = writeln!(
+            out, "%%MatrixMarket matrix {} real {}",
+            if format == MtxFormat::Coordinate { "coordinate" } else { "array" },
+            if symmetric { "symmetric" } else { "general" }
+        );
+
+        let mut places = Vec::new();
+        for j in 0..cols {
+            for i in (if symmetric { j } else { 0 })..rows {
+                places.push((i, j))
+            }
+        };
+        match format {
+            MtxFormat::Coordinate => {
+                let entries = places.into_iter().filter(|(i, j)| self.get(*j, *i) != 0.0).collect::<Vec<_>>();
+                let _ = writeln!(out, "{} {} {}", rows, cols, entries.len());
+                for (i, j) in entries {
+                    let _ = writeln!(out, "{} {} {}", i + 1, j + 1, self.get(j, i));
+                }
+            },
+            MtxFormat::Array => {
+                let _ = writeln!(out, "{} {}", rows, cols);
+                for (i, j) in places {
+                    let _ = writeln!(out, "{}", self.get(j, i));
+                }
+            }
+        };
+        Ok(out)
+    }
+
+    pub fn read_mtx(path: &str) -> Result<Self, TextError> {
+        Self::from_mtx_str(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn write_mtx(&self, path: &str, format: MtxFormat, symmetry: MtxSymmetry) -> Result<(), TextError> {
+        Ok(std::fs::write(path, self.to_mtx_string(format, symmetry)?)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Matrix {
+        Matrix::from([[1.0, -2.5, 3.0], [0.0, 4.25, -6.0]])
+    }
+
+    #[test]
+    fn txt_round_trip() {
+        for delimiter in [',', ' ', '\t'] {
+            let text = sample().to_txt_string(delimiter);
+            crate::assert_matrix_close!(Matrix::from_txt_str(&text).unwrap(), sample())
+        }
+    }
+
+    #[test]
+    fn txt_comments_and_blank_lines() {
+        let text = "# a, b, c\n\n1 -2.5 3\n  # another, comment\n0 4.25 -6\n\n";
+        crate::assert_matrix_close!(Matrix::from_txt_str(text).unwrap(), sample());
+        let text = "# a b c\n1, -2.5, 3\n0,4.25,-6\n";
+        crate::assert_matrix_close!(Matrix::from_txt_str(text).unwrap(), sample());
+        assert_eq!(Matrix::from_txt_str("# nothing\n\n").unwrap().shape(), (0, 0))
+    }
+
+    #[test]
+    fn txt_errors() {
+        let err = Matrix::from_txt_str("# header\n1 2\n3  x\n").unwrap_err();
+        assert_eq!((err.line, err.column), (3, 4));
+        assert_eq!(err.message, "invalid number \"x\"");
+        let err = Matrix::from_txt_str("1, 2\n3, 4, 5\n").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 1));
+        // an empty field between two commas
+        let err = Matrix::from_txt_str("1,,2\n").unwrap_err();
+        assert_eq!((err.line, err.column), (1, 3))
+    }
+
+    #[test]
+    fn mtx_round_trip() {
+        let symmetric = Matrix::from([[2.0, -1.0, 0.0], [-1.0, 2.0, 0.5], [0.0, 0.5, 3.0]]);
+        for format in [MtxFormat::Coordinate, MtxFormat::Array] {
+            let text = sample().to_mtx_string(format, MtxSymmetry::General).unwrap();
+            crate::assert_matrix_close!(Matrix::from_mtx_str(&text).unwrap(), sample());
+            let text = symmetric.to_mtx_string(format, MtxSymmetry::Symmetric).unwrap();
+            assert!(text.lines().next().unwrap().ends_with("symmetric"));
+            crate::assert_matrix_close!(Matrix::from_mtx_str(&text).unwrap(), symmetric)
+        }
+        assert!(matches!(sample().to_mtx_string(MtxFormat::Array, MtxSymmetry::Symmetric), Err(TextError::Unsupported(_))))
+    }
+
+    #[test]
+    fn mtx_coordinate() {
+        let text = "%%MatrixMarket matrix coordinate real general\n% comment\n2 3 3\n1 1 1\n1 2 -2.5\n2 3 -6\n";
+        let expected = Matrix::from([[1.0, -2.5, 0.0], [0.0, 0.0, -6.0]]);
+        crate::assert_matrix_close!(Matrix::from_mtx_str(text).unwrap(), expected)
+    }
+
+    #[test]
+    fn mtx_array_is_column_major() {
+        let text = "%%MatrixMarket matrix array real general\n2 3\n1\n0\n-2.5\n4.25\n3\n-6\n";
+        crate::assert_matrix_close!(Matrix::from_mtx_str(text).unwrap(), sample())
+    }
+
+    #[test]
+    fn mtx_symmetric() {
+        let text = "%%MatrixMarket matrix coordinate real symmetric\n3 3 3\n1 1 2\n2 1 -1\n3 2 0.5\n";
+        let expected = Matrix::from([[2.0, -1.0, 0.0], [-1.0, 0.0, 0.5], [0.0, 0.5, 0.0]]);
+        crate::assert_matrix_close!(Matrix::from_mtx_str(text).unwrap(), expected);
+        let text = "%%MatrixMarket matrix array real symmetric\n2 2\n1\n2\n3\n";
+        crate::assert_matrix_close!(Matrix::from_mtx_str(text).unwrap(), Matrix::from([[1.0, 2.0], [2.0, 3.0]]))
+    }
+
+    #[test]
+    fn mtx_pattern() {
+        let text = "%%MatrixMarket matrix coordinate pattern general\n2 2 2\n1 2\n2 1\n";
+        crate::assert_matrix_close!(Matrix::from_mtx_str(text).unwrap(), Matrix::from([[0.0, 1.0], [1.0, 0.0]]));
+        let text = "%%MatrixMarket matrix array pattern general\n1 1\n1\n";
+        assert!(matches!(Matrix::from_mtx_str(text), Err(TextError::Unsupported(_))))
+    }
+
+    #[test]
+    fn mtx_errors() {
+        assert!(matches!(Matrix::from_mtx_str("2 2\n"), Err(TextError::Parse(ParseError { line: 1, .. }))));
+        let text = "%%MatrixMarket matrix coordinate real general\n2 2 1\n3 1 1.0\n";
+        match Matrix::from_mtx_str(text) {
+            Err(TextError::Parse(err)) => assert_eq!((err.line, err.column, err.message.as_str()), (3, 1, "index (3, 1) out of range")),
+            other => panic!("expected a parse error, got {:?}", other.map(|i| i.shape()))
+        };
+        let text = "%%MatrixMarket matrix coordinate real general\n2 2 2\n1 1 1.0\n";
+        assert!(matches!(Matrix::from_mtx_str(text), Err(TextError::Parse(ParseError { line: 2, column: 5, .. }))))
+    }
+}
@@ -0,0 +1,256 @@
+use super::{Matrix, Vector};
+
+// Every constructor returns a homogeneous matrix, 3x3 for 2-d and 4x4 for 3-d transforms,
+// acting on column vectors, so `a * b` applies b first.
+impl Matrix {
+    pub fn rotation_2d(angle: f64) -> Self {
+        let (sin, cos) = angle.sin_cos();
+        Matrix::from([
+            [cos, -sin, 0.0],
+            [sin, cos, 0.0],
+            [0.0, 0.0, 1.0]
+        ])
+    }
+
+    pub fn scaling_2d(sx: f64, sy: f64) -> Self {
+        Matrix::from([
+            [sx, 0.0, 0.0],
+            [0.0, sy, 0.0],
+            [0.0, 0.0, 1.0]
+        ])
+    }
+
+    // x' = x + shx * y, y' = y + shy * x
+    pub fn shear_2d(shx: f64, shy: f64) -> Self {
+        Matrix::from([
+            [1.0, shx, 0.0],
+            [shy, 1.0, 0.0],
+            [0.0, 0.0, 1.0]
+        ])
+    }
+
+    pub fn translation_2d(tx: f64, ty: f64) -> Self {
+        Matrix::from([
+            [1.0, 0.0, tx],
+            [0.0, 1.0, ty],
+            [0.0, 0.0, 1.0]
+        ])
+    }
+
+    // Rodrigues' formula, the axis does not need to be normalised
+    pub fn rotation_axis_angle(axis: &Vector, angle: f64) -> Self {
+        if axis.len() != 3 { panic!("The axis must be 3-d!") }
+        let norm = (axis.clone() * axis.clone()).sqrt();
+        if norm == 0.0 { panic!("The axis must not be zero!") }
+        let (x, y, z) = (axis.get_val()[0] / norm, axis.get_val()[1] / norm, axis.get_val()[2] / norm);
+        let (sin, cos) = angle.sin_cos();
+        let c = 1.0 - cos;
+        Matrix::from([
+            [cos + x * x * c, x * y * c - z * sin, x * z * c + y * sin, 0.0],
+            [y * x * c + z * sin, cos + y * y * c, y * z * c - x * sin, 0.0],
+            [z * x * c - y * sin, z * y * c + x * sin, cos + z * z * c, 0.0],
+            [0.0, 0.0, 0.0, 1.0]
+        ])
+    }
+
+    // roll about x, then pitch about y, then yaw about z: R = Rz(yaw) * Ry(pitch) * Rx(roll)
+    pub fn rotation_euler(roll: f64, pitch: f64, yaw: f64) -> Self {
+        Matrix::rotation_axis_angle(&Vector::from([0.0, 0.0, 1.0]), yaw)
+            * Matrix::rotation_axis_angle(&Vector::from([0.0, 1.0, 0.0]), pitch)
+            * Matrix::rotation_axis_angle(&Vector::from([1.0, 0.0, 0.0]), roll)
+    }
+
+    // the quaternion is (w, x, y, z) and gets normalised first
+    pub fn rotation_quaternion(quaternion: &Vector) -> Self {
+        if quaternion.len() != 4 { panic!("A quaternion has 4 components!") }
+        let norm = (quaternion.clone() * quaternion.clone()).sqrt();
+        if norm == 0.0 { panic!("The quaternion must not be zero!") }
+        let q = quaternion.strech(1.0 / norm);
+        let (w, x, y, z) = (q.get_val()[0], q.get_val()[1], q.get_val()[2], q.get_val()[3]);
+        Matrix::from([
+            [1.0 - 2.0 * (y * y + z * z), 2.0 * (x * y - z * w), 2.0 * (x * z + y * w), 0.0],
+            [2.0 * (x * y + z * w), 1.0 - 2.0 * (x * x + z * z), 2.0 * (y * z - x * w), 0.0],
+            [2.0 * (x * z - y * w), 2.0 * (y * z + x * w), 1.0 - 2.0 * (x * x + y * y), 0.0],
+            [0.0, 0.0, 0.0, 1.0]
+        ])
+    }
+
+    pub fn scaling_3d(sx: f64, sy: f64, sz: f64) -> Self {
+        Matrix::from([
+            [sx, 0.0, 0.0, 0.0],
+            [0.0, sy, 0.0, 0.0],
+            [0.0, 0.0, sz, 0.0],
+            [0.0, 0.0, 0.0, 1.0]
+        ])
+    }
+
+    // shear[i][j] is how much coordinate j adds to coordinate i, the diagonal is ignored
+    pub fn shear_3d(shear: [[f64; 3]; 3]) -> Self {
+        let mut out = Matrix::identity(4);
+        for (i, row) in shear.iter().enumerate() {
+            for (j, val) in row.iter().enumerate() {
+                if i != j {
+                    out.change_place((j, i), *val)
+                }
+            }
+        };
+        out
+    }
+
+    pub fn translation_3d(tx: f64, ty: f64, tz: f64) -> Self {
+        Matrix::from([
+            [1.0, 0.0, 0.0, tx],
+            [0.0, 1.0, 0.0, ty],
+            [0.0, 0.0, 1.0, tz],
+            [0.0, 0.0, 0.0, 1.0]
+        ])
+    }
+
+    // OpenGL style: a camera looking down -z, the view frustum mapped onto the [-1, 1] cube
+    pub fn perspective(fov_y: f64, aspect: f64, near: f64, far: f64) -> Self {
+        if near <= 0.0 || far <= near { panic!("Need 0 < near < far!") }
+        let f = 1.0 / (fov_y / 2.0).tan();
+        Matrix::from([
+            [f / aspect, 0.0, 0.0, 0.0],
+            [0.0, f, 0.0, 0.0],
+            [0.0, 0.0, (far + near) / (near - far), 2.0 * far * near / (near - far)],
+            [0.0, 0.0, -1.0, 0.0]
+        ])
+    }
+
+    // n-d points on a matrix with n columns are mapped linearly. Points one coordinate short
+    // get w = 1 appended: an n x (n+1) matrix, or a square one ending in [0 ... 0 1], is affine
+    // and only drops w again, any other square matrix is projective and divides by w.
+    pub fn apply_to_points(&self, points: &[Vector]) -> Result<Vec<Vector>, TransformError> {
+        let (cols, rows) = self.shape();
+        let affine = rows + 1 == cols
+            || rows == cols && (0..cols).all(|x| self.get(x, rows - 1) == if x + 1 == cols { 1.0 } else { 0.0 });
+        let mut out = Vec::new();
+        for (index, point) in points.iter().enumerate() {
+            let mode = if point.len() == cols {
+                Mode::Linear
+            } else if point.len() + 1 == cols && affine {
+                Mode::Affine
+            } else if point.len() + 1 == cols && rows == cols {
+                Mode::Projective
+            } else {
+                return Err(TransformError::Shape { matrix: (cols, rows), point: point.len() })
+            };
+
+            let mut input = point.get_val().clone();
+            input.resize(cols, 1.0);
+            let mut buf = Vec::new();
+            for y in 0..rows {
+                let mut sum = 0.0;
+                for (x, val) in input.iter().enumerate() {
+                    sum += self.get(x, y) * val
+                };
+                buf.push(sum)
+            };
+
+            match mode {
+                Mode::Linear => (),
+                // the n x (n+1) form has no w row to drop
+                Mode::Affine => buf.truncate(cols - 1),
+                Mode::Projective => {
+                    let w = buf.pop().unwrap_or(1.0);
+                    if w == 0.0 { return Err(TransformError::AtInfinity { index }) }
+                    for i in buf.iter_mut() {
+                        *i /= w
+                    }
+                }
+            }
+            out.push(Vector::from_vec(buf))
+        };
+        Ok(out)
+    }
+}
+
+enum Mode {
+    Linear,
+    Affine,
+    Projective
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TransformError {
+    // the matrix shape is (cols, rows)
+    Shape { matrix: (usize, usize), point: usize },
+    // a projective matrix sent the point with this index to w = 0
+    AtInfinity { index: usize }
+}
+
+impl std::fmt::Display for TransformError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TransformError::Shape { matrix, point } => write!(f, "a {}x{} matrix cannot transform points of length {}", matrix.1, matrix.0, point),
+            TransformError::AtInfinity { index } => write!(f, "point {} is mapped to infinity", index)
+        }
+    }
+}
+
+impl std::error::Error for TransformError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::FRAC_PI_2;
+
+    fn check(out: Result<Vec<Vector>, TransformError>, expected: &[Vector]) {
+        let out = out.unwrap();
+        assert_eq!(out.len(), expected.len());
+        for (a, b) in out.iter().zip(expected.iter()) {
+            crate::assert_vector_close!(a, b, 1e-12, 1e-12)
+        }
+    }
+
+    #[test]
+    fn composition_applies_right_to_left() {
+        let transform = Matrix::translation_2d(1.0, 1.0) * Matrix::rotation_2d(FRAC_PI_2) * Matrix::scaling_2d(2.0, 3.0);
+        let points = [Vector::from([1.0, 0.0]), Vector::from([0.0, 1.0])];
+        check(transform.apply_to_points(&points), &[Vector::from([1.0, 3.0]), Vector::from([-2.0, 1.0])]);
+
+        let transform = Matrix::translation_3d(0.0, 0.0, 5.0) * Matrix::rotation_axis_angle(&Vector::from([0.0, 0.0, 2.0]), FRAC_PI_2);
+        check(transform.apply_to_points(&[Vector::from([1.0, 0.0, 0.0])]), &[Vector::from([0.0, 1.0, 5.0])])
+    }
+
+    #[test]
+    fn linear_and_short_affine_matrices() {
+        // points as long as the matrix is wide are not homogeneous
+        let transform = Matrix::rotation_2d(FRAC_PI_2);
+        check(transform.apply_to_points(&[Vector::from([1.0, 0.0, 0.0])]), &[Vector::from([0.0, 1.0, 0.0])]);
+        // a 2 x 3 affine matrix without the [0 0 1] row
+        let transform = Matrix::from([[2.0, 0.0, 5.0], [0.0, 1.0, -1.0]]);
+        check(transform.apply_to_points(&[Vector::from([1.0, 1.0])]), &[Vector::from([7.0, 0.0])])
+    }
+
+    #[test]
+    fn perspective_divides_by_w() {
+        let transform = Matrix::perspective(FRAC_PI_2, 1.0, 1.0, 3.0);
+        let points = [Vector::from([0.0, 0.0, -1.0]), Vector::from([0.0, 0.0, -3.0]), Vector::from([1.0, 1.0, -2.0])];
+        // the near and far planes land on z = -1 and z = 1
+        check(transform.apply_to_points(&points), &[
+            Vector::from([0.0, 0.0, -1.0]),
+            Vector::from([0.0, 0.0, 1.0]),
+            Vector::from([0.5, 0.5, 0.5])
+        ]);
+        let points = [Vector::from([0.0, 0.0, -1.0]), Vector::from([1.0, 0.0, 0.0])];
+        assert_eq!(transform.apply_to_points(&points).unwrap_err(), TransformError::AtInfinity { index: 1 })
+    }
+
+    #[test]
+    fn dimension_mismatch() {
+        let transform = Matrix::translation_2d(1.0, 1.0);
+        for length in [1, 4] {
+            let points = [Vector::from([0.0, 0.0]), Vector::from_vec(vec![0.0; length])];
+            assert_eq!(transform.apply_to_points(&points).unwrap_err(), TransformError::Shape { matrix: (3, 3), point: length })
+        };
+        // a 3 x 2 matrix has no homogeneous reading
+        let transform = Matrix::from([[1.0, 0.0], [0.0, 1.0], [1.0, 1.0]]);
+        assert_eq!(transform.apply_to_points(&[Vector::from([1.0])]).unwrap_err(), TransformError::Shape { matrix: (2, 3), point: 1 });
+        assert_eq!(
+            TransformError::Shape { matrix: (2, 3), point: 1 }.to_string(),
+            "a 3x2 matrix cannot transform points of length 1"
+        )
+    }
+}
@@ -1,6 +1,25 @@
-use super::{Determinant, Matrix, DeterNum, func};
+use super::{Determinant, Matrix, DeterNum, approx, func};
+use func::Softmax;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum VectorError {
+    Arity { expected: usize, found: usize },
+    Shape { expected: usize, found: usize }
+}
+
+impl std::fmt::Display for VectorError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VectorError::Arity { expected, found } => write!(f, "expected {} vectors, found {}", expected, found),
+            VectorError::Shape { expected, found } => write!(f, "expected vectors of length {}, found {}", expected, found)
+        }
+    }
+}
+
+impl std::error::Error for VectorError {}
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(transparent))]
 pub struct Vector {
     val: Vec<f64>
 }
@@ -40,7 +59,7 @@ impl Vector {
         self.shape() == rhs.shape()
     }
 
-    pub fn oper_with(&self, rhs: Vector, op: &(dyn Fn(f64, f64) -> f64)) -> Vector {
+    pub fn oper_with(&self, rhs: Vector, op: &dyn Fn(f64, f64) -> f64) -> Vector {
         if ! self.same_shape(&rhs) { panic!("The shapes are not matched!") }
         let mut out = self.clone();
         for i in 0..out.shape() {
@@ -49,7 +68,7 @@ impl Vector {
         out
     }
 
-    pub fn oper(&self, op: &(dyn Fn(f64) -> f64)) -> Vector {
+    pub fn oper(&self, op: &dyn Fn(f64) -> f64) -> Vector {
         let mut out = self.clone();
         for i in 0..out.shape() {
             out.val[i] = op(out.val[i])
@@ -57,13 +76,13 @@ impl Vector {
         out
     }
 
-    pub fn oper_assign(&mut self, op: &(dyn Fn(f64) -> f64)) {
+    pub fn oper_assign(&mut self, op: &dyn Fn(f64) -> f64) {
         for i in self.val.iter_mut() {
             *i = op(*i)
         }
     }
 
-    pub fn oper_with_assign(&mut self, rhs: Vector, op: &(dyn Fn(f64, f64) -> f64)) {
+    pub fn oper_with_assign(&mut self, rhs: Vector, op: &dyn Fn(f64, f64) -> f64) {
         if ! self.same_shape(&rhs) { panic!("The shapes are not matched!") }
         for i in 0..self.shape() {
             self.val[i] = op(self.val[i], rhs.val[i])
@@ -110,41 +129,96 @@ impl Vector {
         &self.val
     }
 
+    // the 3-d cross product, see cross_n for other dimensions
     pub fn cross_product(&self, rhs: Vector) -> Vector {
-        if ! self.same_shape(&rhs) { panic!("Mismatched shapes!") }
+        match Self::cross_n(&[self, &rhs]) {
+            Ok(vec) => vec,
+            Err(err) => panic!("{}", err)
+        }
+    }
 
-        let length = self.val.len();
-        let mut buf = Vec::new();
-        for _ in 0..length {
-            let mut buf_1 = Vec::new();
-            for _ in 0..length {
-                buf_1.push(1.0)
-            };
-            buf.push(buf_1)
+    // the vector orthogonal to n - 1 vectors in n dimensions, from the formal determinant
+    // with the basis vectors in its last row, so that e1 x ... x e(n-1) = en
+    pub fn cross_n(vectors: &[&Vector]) -> Result<Vector, VectorError> {
+        let length = match vectors.first() {
+            Some(first) => first.len(),
+            None => return Err(VectorError::Arity { expected: 1, found: 0 })
         };
-        let mut deter = Determinant::from_vec(buf);
+        if vectors.len() + 1 != length {
+            return Err(VectorError::Arity { expected: length.saturating_sub(1), found: vectors.len() })
+        }
+        Self::check_lengths(vectors, length)?;
 
+        let mut buf = Vec::new();
+        for vec in vectors.iter() {
+            buf.push(vec.val.iter().map(|i| DeterNum::Float(*i)).collect())
+        };
+        let mut basis = Vec::new();
         for i in 0..length {
-            let mut buf = Vec::new();
-            for j in 0..length {
-                if j == i {
-                    buf.push(1.0)
-                } else {
-                    buf.push(0.0)
-                }
-            }
-            deter.change_place_vector(i, 0, Self::from_vec(buf))
+            let mut unit = vec![0.0; length];
+            unit[i] = 1.0;
+            basis.push(DeterNum::Vec(Vector::from_vec(unit)))
         };
+        buf.push(basis);
 
-        for i in 0..length {
-            deter.change_place_float(i, 1, self.val[i]);
-            deter.change_place_float(i, 2, rhs.val[i])
+        match Determinant::from_vec_deter(buf).cal() {
+            DeterNum::Vec(vec) => Ok(vec),
+            _ => panic!("Shouldn't be here!")
+        }
+    }
+
+    // a · (b × c)
+    pub fn triple_product(a: &Vector, b: &Vector, c: &Vector) -> Result<f64, VectorError> {
+        Self::signed_volume(&[a, b, c])
+    }
+
+    // the determinant of n vectors in n dimensions, positive for a right-handed set
+    pub fn signed_volume(vectors: &[&Vector]) -> Result<f64, VectorError> {
+        let length = match vectors.first() {
+            Some(first) => first.len(),
+            None => return Err(VectorError::Arity { expected: 1, found: 0 })
         };
-    
-        match deter.cal() {
-            DeterNum::Float(_) => panic!("Shouldn't be here!"),
-            DeterNum::Vec(vec) => vec
+        if vectors.len() != length {
+            return Err(VectorError::Arity { expected: length, found: vectors.len() })
         }
+        Self::check_lengths(vectors, length)?;
+        match Determinant::from_vec(vectors.iter().map(|i| i.val.clone()).collect()).cal() {
+            DeterNum::Float(num) => Ok(num),
+            _ => panic!("Shouldn't be here!")
+        }
+    }
+
+    // -1, 0 or 1 depending on the handedness of n vectors in n dimensions
+    pub fn orientation(vectors: &[&Vector]) -> Result<i8, VectorError> {
+        let volume = Self::signed_volume(vectors)?;
+        Ok(if volume > 0.0 { 1 } else if volume < 0.0 { -1 } else { 0 })
+    }
+
+    // the k-dimensional volume of the parallelotope spanned by k vectors, sqrt(det(G)) with G the Gram matrix
+    pub fn volume(vectors: &[&Vector]) -> Result<f64, VectorError> {
+        let length = match vectors.first() {
+            Some(first) => first.len(),
+            None => return Ok(0.0)
+        };
+        Self::check_lengths(vectors, length)?;
+        let mut gram = Vec::new();
+        for a in vectors.iter() {
+            gram.push(vectors.iter().map(|b| (*a).clone() * (*b).clone()).collect())
+        };
+        match Determinant::from_vec(gram).cal() {
+            // rounding can leave a tiny negative value for degenerate sets
+            DeterNum::Float(num) => Ok(num.max(0.0).sqrt()),
+            _ => panic!("Shouldn't be here!")
+        }
+    }
+
+    fn check_lengths(vectors: &[&Vector], length: usize) -> Result<(), VectorError> {
+        for vec in vectors.iter() {
+            if vec.len() != length {
+                return Err(VectorError::Shape { expected: length, found: vec.len() })
+            }
+        };
+        Ok(())
     }
 
     pub fn iter(&self) -> std::slice::Iter<'_, f64> {
@@ -159,6 +233,10 @@ impl Vector {
         self.val.len()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.val.is_empty()
+    }
+
     pub fn max(&self) -> f64 {
         let mut max = f64::MIN;
         for i in self.val.iter() {
@@ -178,6 +256,20 @@ impl Vector {
         };
         min
     }
+
+    pub fn first_mismatch(&self, rhs: &Vector, rtol: f64, atol: f64) -> Option<(usize, f64, f64)> {
+        if ! self.same_shape(rhs) { panic!("The shapes are not matched!") }
+        for (index, (a, b)) in self.val.iter().zip(rhs.val.iter()).enumerate() {
+            if ! approx::close(*a, *b, rtol, atol) {
+                return Some((index, *a, *b))
+            }
+        };
+        None
+    }
+
+    pub fn approx_eq(&self, rhs: &Vector, rtol: f64, atol: f64) -> bool {
+        self.same_shape(rhs) && self.first_mismatch(rhs, rtol, atol).is_none()
+    }
 }
 
 impl std::ops::Add for Vector {
@@ -226,64 +318,156 @@ impl std::fmt::Display for Vector {
     }
 }
 
-impl func::Relu for Vector {
-    fn relu_for(&self) -> Self {
-        let mut out = self.clone();
-        for i in out.iter_mut() {
-            *i = i.relu_back()
-        };
-        out
+impl func::Softmax for Vector {
+    fn soft_for(&self) -> Self {
+        self.soft_for_temp(1.0)
     }
 
-    fn relu_back(&self) -> Self {
-        let mut out = self.clone();
-        for i in out.iter_mut() {
-            *i = i.relu_back()
-        };
-        out
+    fn soft_for_temp(&self, temperature: f64) -> Self {
+        if temperature <= 0.0 { panic!("The temperature must be positive!") }
+        // shifting by the max keeps exp() from overflowing, the result is the same
+        let max = self.max();
+        let buf = self.oper(&| i | { ((i - max) / temperature).exp() });
+        let sum = buf.sum();
+        buf.oper(&| i | { i/sum })
     }
-}
 
-impl func::Sigmoid for Vector {
-    fn sig_for(&self) -> Self {
-        let mut out = self.clone();
-        for i in out.iter_mut() {
-            *i = i.sig_for()
-        };
-        out
+    // x - log(sum(e^x)), with the max pulled out of the log-sum-exp
+    fn log_soft_for(&self) -> Self {
+        let max = self.max();
+        let log_sum = max + self.oper(&| i | { (i - max).exp() }).sum().ln();
+        self.oper(&| i | { i - log_sum })
     }
-    
-    fn sig_back(&self) -> Self {
-        let mut out = self.clone();
-        for i in out.iter_mut() {
-            *i = i.sig_back()
-        };
-        out
+
+    // J^T grad, where J = diag(s) - s s^T
+    fn soft_back(&self, grad: &Self) -> Self {
+        if self.len() != grad.len() { panic!("The lengths are not matched!") }
+        let soft = self.soft_for();
+        let dot = soft.iter().zip(grad.iter()).map(|(i, j)| i * j).sum::<f64>();
+        Vector::from_vec(soft.iter().zip(grad.iter()).map(|(i, j)| i * (j - dot)).collect())
     }
 }
 
-impl func::Tanh for Vector {
-    fn tanh_for(&self) -> Self {
-        let mut out = self.clone();
-        for i in out.iter_mut() {
-            *i = i.tanh_for()
-        };
-        out
+impl func::Activation for Vector {
+    fn forward(&self, kind: func::ActivationKind) -> Self {
+        match kind {
+            func::ActivationKind::Softmax => self.soft_for(),
+            _ => self.oper(&| i | { kind.apply(i) })
+        }
     }
 
-    fn tanh_back(&self) -> Self {
-        let mut out = self.clone();
-        for i in out.iter_mut() {
-            *i = i.tanh_back()
-        };
-        out
+    fn derivative(&self, kind: func::ActivationKind) -> Self {
+        self.oper(&| i | { i.derivative(kind) })
+    }
+
+    fn backward(&self, kind: func::ActivationKind, grad: &Self) -> Self {
+        if self.len() != grad.len() { panic!("The lengths are not matched!") }
+        match kind {
+            func::ActivationKind::Softmax => self.soft_back(grad),
+            _ => Vector::from_vec(self.iter().zip(grad.iter()).map(|(i, j)| kind.derivative(*i) * j).collect())
+        }
     }
 }
 
-impl func::Softmax for Vector {
-    fn soft_for(&self) -> Self {
-        let buf = self.oper(&| i | { i.exp() });
-        let sum = buf.sum();
-        buf.oper(&| i | { i/sum })
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unit(length: usize, index: usize) -> Vector {
+        let mut buf = vec![0.0; length];
+        buf[index] = 1.0;
+        Vector::from_vec(buf)
+    }
+
+    #[test]
+    fn cross_of_the_basis() {
+        let (e1, e2, e3) = (unit(3, 0), unit(3, 1), unit(3, 2));
+        crate::assert_vector_close!(e1.cross_product(e2.clone()), e3);
+        crate::assert_vector_close!(e2.cross_product(e1.clone()), Vector::from([0.0, 0.0, -1.0]));
+        crate::assert_vector_close!(Vector::from([1.0, 2.0, 3.0]).cross_product(Vector::from([4.0, 5.0, 6.0])), Vector::from([-3.0, 6.0, -3.0]));
+        // in 2-d the single vector is turned by a quarter, so that e1 gives e2
+        crate::assert_vector_close!(Vector::cross_n(&[&unit(2, 0)]).unwrap(), unit(2, 1))
+    }
+
+    #[test]
+    fn cross_n_sign_convention() {
+        let e = (0..4).map(|i| unit(4, i)).collect::<Vec<_>>();
+        crate::assert_vector_close!(Vector::cross_n(&[&e[0], &e[1], &e[2]]).unwrap(), e[3]);
+        // swapping two of the factors flips the result
+        crate::assert_vector_close!(Vector::cross_n(&[&e[1], &e[0], &e[2]]).unwrap(), Vector::from([0.0, 0.0, 0.0, -1.0]));
+        // the result is orthogonal to every factor and completes a right-handed set
+        let vectors = [Vector::from([1.0, 2.0, 0.0, 1.0]), Vector::from([0.0, 1.0, 3.0, -1.0]), Vector::from([2.0, 0.0, 1.0, 1.0])];
+        let cross = Vector::cross_n(&[&vectors[0], &vectors[1], &vectors[2]]).unwrap();
+        for vec in vectors.iter() {
+            assert!((vec.clone() * cross.clone()).abs() < 1e-12)
+        };
+        assert_eq!(Vector::orientation(&[&vectors[0], &vectors[1], &vectors[2], &cross]), Ok(1))
+    }
+
+    #[test]
+    fn cross_n_errors() {
+        assert_eq!(Vector::cross_n(&[]).unwrap_err(), VectorError::Arity { expected: 1, found: 0 });
+        let (a, b) = (unit(4, 0), unit(4, 1));
+        assert_eq!(Vector::cross_n(&[&a, &b]).unwrap_err(), VectorError::Arity { expected: 3, found: 2 });
+        let (a, b) = (unit(3, 0), unit(4, 1));
+        assert_eq!(Vector::cross_n(&[&a, &b]).unwrap_err(), VectorError::Shape { expected: 3, found: 4 })
+    }
+
+    #[test]
+    fn triple_product() {
+        let (e1, e2, e3) = (unit(3, 0), unit(3, 1), unit(3, 2));
+        assert_eq!(Vector::triple_product(&e1, &e2, &e3), Ok(1.0));
+        assert_eq!(Vector::triple_product(&e2, &e1, &e3), Ok(-1.0));
+        let (a, b, c) = (Vector::from([1.0, 2.0, 3.0]), Vector::from([4.0, 5.0, 6.0]), Vector::from([7.0, 8.0, 10.0]));
+        assert!((Vector::triple_product(&a, &b, &c).unwrap() + 3.0).abs() < 1e-9);
+        assert_eq!(Vector::triple_product(&a, &b, &unit(2, 0)), Err(VectorError::Shape { expected: 3, found: 2 }))
+    }
+
+    #[test]
+    fn volume_of_fewer_vectors_than_dimensions() {
+        // a 2 x 3 rectangle in the plane z = 1 of 3-d space
+        let (a, b) = (Vector::from([2.0, 0.0, 0.0]), Vector::from([0.0, 3.0, 0.0]));
+        assert!((Vector::volume(&[&a, &b]).unwrap() - 6.0).abs() < 1e-9);
+        // a parallelogram, |a x b|
+        let (a, b) = (Vector::from([1.0, 1.0, 0.0]), Vector::from([0.0, 1.0, 1.0]));
+        assert!((Vector::volume(&[&a, &b]).unwrap() - 3f64.sqrt()).abs() < 1e-9);
+        // a single vector has its length as volume, parallel vectors have none
+        assert!((Vector::volume(&[&Vector::from([3.0, 4.0, 0.0, 0.0])]).unwrap() - 5.0).abs() < 1e-9);
+        assert!(Vector::volume(&[&a, &Vector::from([2.0, 2.0, 0.0])]).unwrap().abs() < 1e-6)
+    }
+
+    #[test]
+    fn softmax_of_extreme_logits() {
+        let logits = Vector::from([1000.0, -1000.0, 999.0]);
+        let soft = logits.soft_for();
+        assert!(soft.iter().all(|i| i.is_finite()));
+        assert!((soft.sum() - 1.0).abs() < 1e-12);
+        let norm = 1.0 + (-1f64).exp();
+        crate::assert_vector_close!(soft, Vector::from([1.0 / norm, 0.0, (-1f64).exp() / norm]));
+        let log_soft = logits.log_soft_for();
+        crate::assert_vector_close!(log_soft, Vector::from([-norm.ln(), -2000.0 - norm.ln(), -1.0 - norm.ln()]));
+        crate::assert_vector_close!(Vector::from([-1000.0, -1000.0]).soft_for(), Vector::from([0.5, 0.5]))
+    }
+
+    #[test]
+    fn softmax_temperature() {
+        let logits = Vector::from([2.0, -1.0, 0.5, 1.0]);
+        crate::assert_vector_close!(logits.soft_for_temp(1.0), logits.soft_for());
+        // flat for a large temperature, one-hot on the max for a small one
+        crate::assert_vector_close!(logits.soft_for_temp(1e6), Vector::from([0.25, 0.25, 0.25, 0.25]), 1e-5, 1e-5);
+        crate::assert_vector_close!(logits.soft_for_temp(1e-3), Vector::from([1.0, 0.0, 0.0, 0.0]));
+        crate::assert_vector_close!(logits.soft_for_temp(0.5), logits.oper(&|i| i * 2.0).soft_for())
+    }
+
+    #[test]
+    #[should_panic(expected = "The temperature must be positive!")]
+    fn softmax_temperature_zero() {
+        Vector::from([1.0, 2.0]).soft_for_temp(0.0);
+    }
+
+    #[test]
+    fn log_softmax_is_log_of_softmax() {
+        let logits = Vector::from([3.0, -2.0, 0.0, 0.7, -0.1]);
+        crate::assert_vector_close!(logits.log_soft_for(), logits.soft_for().oper(&|i| i.ln()), 1e-12, 1e-12)
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,223 @@
+use crate::func::Softmax;
+use crate::lin_alg::Matrix;
+
+// keeps log() away from zero for probabilities
+const EPS: f64 = 1e-12;
+
+// every column of a batch is one sample, the same layout softmax normalises over
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LossKind {
+    Mse,
+    Mae,
+    // quadratic within delta, linear outside
+    Huber(f64),
+    BinaryCrossEntropy,
+    // from probabilities, e.g. after soft_for
+    CrossEntropy,
+    // from raw logits, with the softmax folded in
+    CrossEntropyLogits,
+    // from log-probabilities, e.g. after log_soft_for
+    Nll,
+    // targets are -1 or 1
+    Hinge
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Reduction {
+    #[default]
+    Mean,
+    Sum,
+    None
+}
+
+#[derive(Clone, Debug)]
+pub enum LossValue {
+    Scalar(f64),
+    // element-wise losses keep the batch shape, class losses give one row with an entry per sample
+    Each(Matrix)
+}
+
+impl LossValue {
+    pub fn scalar(&self) -> f64 {
+        match self {
+            LossValue::Scalar(val) => *val,
+            LossValue::Each(_) => panic!("The loss is not reduced!")
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Loss {
+    pub kind: LossKind,
+    pub reduction: Reduction
+}
+
+// one column per label with a 1 in the label's row
+pub fn one_hot(labels: &[usize], classes: usize) -> Matrix {
+    let mut out = Matrix::zeros((labels.len(), classes));
+    for (col, label) in labels.iter().enumerate() {
+        if *label >= classes { panic!("The label is out of the classes!") }
+        out.change_place((col, *label), 1.0)
+    };
+    out
+}
+
+fn cols(matrix: &Matrix) -> Vec<Vec<f64>> {
+    matrix.t().get_val()
+}
+
+impl Loss {
+    pub fn new(kind: LossKind) -> Self {
+        Self { kind, reduction: Reduction::default() }
+    }
+
+    pub fn reduction(mut self, reduction: Reduction) -> Self {
+        self.reduction = reduction;
+        self
+    }
+
+    fn is_elementwise(&self) -> bool {
+        !matches!(self.kind, LossKind::CrossEntropy | LossKind::CrossEntropyLogits | LossKind::Nll)
+    }
+
+    // the loss of every element, or of every sample for the class losses
+    fn unreduced(&self, pred: &Matrix, target: &Matrix) -> Matrix {
+        let per_sample = match self.kind {
+            LossKind::Mse => return pred.oper_with(target.clone(), &|p, t| (p - t) * (p - t)),
+            LossKind::Mae => return pred.oper_with(target.clone(), &|p, t| (p - t).abs()),
+            LossKind::Huber(delta) => return pred.oper_with(target.clone(), &|p, t| {
+                let d = (p - t).abs();
+                if d <= delta { 0.5 * d * d } else { delta * (d - 0.5 * delta) }
+            }),
+            LossKind::BinaryCrossEntropy => return pred.oper_with(target.clone(), &|p, t| {
+                let p = p.clamp(EPS, 1.0 - EPS);
+                -(t * p.ln() + (1.0 - t) * (1.0 - p).ln())
+            }),
+            LossKind::Hinge => return pred.oper_with(target.clone(), &|p, t| (1.0 - t * p).max(0.0)),
+            LossKind::CrossEntropy => pred.oper_with(target.clone(), &|p, t| -t * p.max(EPS).ln()),
+            LossKind::CrossEntropyLogits => pred.log_soft_for().oper_with(target.clone(), &|p, t| -t * p),
+            LossKind::Nll => pred.oper_with(target.clone(), &|p, t| -t * p)
+        };
+        Matrix::from_vec(vec![cols(&per_sample).iter().map(|i| i.iter().sum()).collect()])
+    }
+
+    pub fn forward(&self, pred: &Matrix, target: &Matrix) -> LossValue {
+        if !pred.same_shape(target) { panic!("The shapes are not matched!") }
+        let each = self.unreduced(pred, target);
+        match self.reduction {
+            Reduction::Mean => {
+                let (x, y) = each.shape();
+                LossValue::Scalar(each.iter().map(|i| i.sum()).sum::<f64>() / (x * y) as f64)
+            },
+            Reduction::Sum => LossValue::Scalar(each.iter().map(|i| i.sum()).sum()),
+            Reduction::None => LossValue::Each(each)
+        }
+    }
+
+    // dL/dpred, the unreduced loss is treated as summed
+    pub fn backward(&self, pred: &Matrix, target: &Matrix) -> Matrix {
+        if !pred.same_shape(target) { panic!("The shapes are not matched!") }
+        let grad = match self.kind {
+            LossKind::Mse => pred.oper_with(target.clone(), &|p, t| 2.0 * (p - t)),
+            LossKind::Mae => pred.oper_with(target.clone(), &|p, t| {
+                if p > t { 1.0 } else if p < t { -1.0 } else { 0.0 }
+            }),
+            LossKind::Huber(delta) => pred.oper_with(target.clone(), &|p, t| (p - t).clamp(-delta, delta)),
+            LossKind::BinaryCrossEntropy => pred.oper_with(target.clone(), &|p, t| {
+                let p = p.clamp(EPS, 1.0 - EPS);
+                (p - t) / (p * (1.0 - p))
+            }),
+            LossKind::Hinge => pred.oper_with(target.clone(), &|p, t| if t * p < 1.0 { -t } else { 0.0 }),
+            LossKind::CrossEntropy => pred.oper_with(target.clone(), &|p, t| -t / p.max(EPS)),
+            LossKind::CrossEntropyLogits => {
+                // softmax(z) * sum(t) - t, which is softmax(z) - t for one-hot targets
+                let sums = cols(target).iter().map(|i| i.iter().sum::<f64>()).collect::<Vec<_>>();
+                let mut soft = pred.soft_for();
+                for (x, sum) in sums.iter().enumerate() {
+                    for y in 0..soft.shape().1 {
+                        soft.change_place((x, y), soft.get(x, y) * sum)
+                    }
+                };
+                soft - target.clone()
+            },
+            LossKind::Nll => target.oper(&|t| -t)
+        };
+        match self.reduction {
+            Reduction::Mean => {
+                let (x, y) = pred.shape();
+                // the mean runs over samples for the class losses and over elements otherwise
+                let count = if self.is_elementwise() { x * y } else { x };
+                grad.strech(1.0 / count as f64)
+            },
+            Reduction::Sum | Reduction::None => grad
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gradcheck::gradcheck;
+    use crate::gradcheck::testing::sample;
+
+    #[test]
+    fn known_values() {
+        let pred = Matrix::from([[1.0, 2.0], [0.0, -1.0]]);
+        let target = Matrix::from([[0.0, 2.0], [1.0, 1.0]]);
+        assert_eq!(Loss::new(LossKind::Mse).forward(&pred, &target).scalar(), 1.5);
+        assert_eq!(Loss::new(LossKind::Mae).reduction(Reduction::Sum).forward(&pred, &target).scalar(), 4.0);
+        assert_eq!(Loss::new(LossKind::Huber(1.0)).forward(&pred, &target).scalar(), (0.5 + 0.5 + 1.5) / 4.0);
+        match Loss::new(LossKind::Mse).reduction(Reduction::None).forward(&pred, &target) {
+            LossValue::Each(each) => crate::assert_matrix_close!(each, Matrix::from([[1.0, 0.0], [1.0, 4.0]])),
+            LossValue::Scalar(_) => panic!("Should be unreduced!")
+        };
+        // the class losses give one entry per sample column, ln 2 for two equal logits
+        let logits = Matrix::from([[0.0, 3.0], [0.0, 1.0]]);
+        let classes = one_hot(&[0, 1], 2);
+        match Loss::new(LossKind::CrossEntropyLogits).reduction(Reduction::None).forward(&logits, &classes) {
+            LossValue::Each(each) => crate::assert_matrix_close!(each, Matrix::from([[2f64.ln(), 2.0 + (-2f64).exp().ln_1p()]])),
+            LossValue::Scalar(_) => panic!("Should be unreduced!")
+        }
+    }
+
+    #[test]
+    fn logits_match_probabilities() {
+        let logits = sample((4, 3), 1, 2.0);
+        let classes = one_hot(&[2, 0, 1, 2], 3);
+        let from_logits = Loss::new(LossKind::CrossEntropyLogits).forward(&logits, &classes).scalar();
+        let from_soft = Loss::new(LossKind::CrossEntropy).forward(&logits.soft_for(), &classes).scalar();
+        let from_log = Loss::new(LossKind::Nll).forward(&logits.log_soft_for(), &classes).scalar();
+        assert!((from_logits - from_soft).abs() < 1e-12);
+        assert!((from_logits - from_log).abs() < 1e-12)
+    }
+
+    #[test]
+    fn gradients() {
+        let (shape, labels) = ((4, 3), [2, 0, 1, 2]);
+        let logits = sample(shape, 1, 2.0);
+        let classes = one_hot(&labels, 3);
+        let cases = [
+            (LossKind::Mse, logits.clone(), sample(shape, 2, 1.0)),
+            (LossKind::Mae, logits.clone(), sample(shape, 2, 1.0)),
+            (LossKind::Huber(0.5), logits.clone(), sample(shape, 2, 1.0)),
+            (LossKind::BinaryCrossEntropy, sample(shape, 3, 0.4).oper(&|i| i + 0.5), sample(shape, 4, 0.5).oper(&|i| i + 0.5)),
+            (LossKind::CrossEntropy, logits.soft_for(), classes.clone()),
+            (LossKind::CrossEntropyLogits, logits.clone(), classes.clone()),
+            (LossKind::Nll, logits.log_soft_for(), classes.clone()),
+            (LossKind::Hinge, logits.clone(), sample(shape, 5, 1.0).oper(&|i| if i > 0.0 { 1.0 } else { -1.0 }))
+        ];
+        for (kind, pred, target) in cases {
+            for reduction in [Reduction::Mean, Reduction::Sum] {
+                let loss = Loss::new(kind).reduction(reduction);
+                let check = gradcheck(|p| loss.forward(p, &target).scalar(), &pred, &loss.backward(&pred, &target));
+                assert!(check.passed(1e-5, 1e-7), "{:?} {:?}: {}", kind, reduction, check)
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "The label is out of the classes!")]
+    fn one_hot_label_out_of_range() {
+        one_hot(&[0, 3], 3);
+    }
+}
@@ -1,40 +1,68 @@
-use crate::func::{Sigmoid, Softmax};
+use nn_rust::func::ActivationKind;
+use nn_rust::layer::{Dense, Initializer};
+use nn_rust::loss::{Loss, LossKind};
+use nn_rust::model::Sequential;
+use nn_rust::optim::Sgd;
+use nn_rust::trainer::Trainer;
+use nn_rust::{data_pro, geometry, lin_alg};
 
-mod lin_alg;
-mod func;
-mod data_pro;
+fn usage(message: &str) -> ! {
+    eprintln!("error: {}", message);
+    std::process::exit(2)
+}
 
-const LEARNING_RATE: f64 = 0.1;
+// the value after `--name` on the command line, or the default when it isn't given
+fn arg<T: std::str::FromStr>(name: &str, default: T) -> T {
+    let args = std::env::args().collect::<Vec<_>>();
+    match args.iter().position(|i| *i == format!("--{}", name)) {
+        Some(index) => args.get(index + 1)
+            .and_then(|i| i.parse().ok())
+            .unwrap_or_else(|| usage(&format!("--{} needs a valid value", name))),
+        None => default
+    }
+}
 
 fn main() {
-    // let mut dataset = data_pro::DataSet::read_from("D:/code/nn_rust/data/mnist_test_short.csv").slice(0, 10).data();
-
-    // dataset.restrict(-50.0, 50.0);
-    // println!("{}", dataset);
-
-    // let hidden_layer = lin_alg::Matrix::new((10, 784));
-
-    // let out = dataset * hidden_layer;
-
-    // let sig = out.sig_for();
-    
-    // let posibility = sig.soft_for();
+    // e.g. --data D:/code/nn_rust/data/mnist_test_short.csv --lr 0.05 --epochs 5
+    let path = arg("data", String::new());
+    if !path.is_empty() {
+        let dataset = data_pro::DataSet::read_from(&path);
+        if dataset.is_empty() { usage(&format!("{} has no samples", path)) }
+        let val_split = arg("val-split", 0.1);
+        if !(0.0..1.0).contains(&val_split) { usage("--val-split must be in [0, 1)") }
+        // keep at least one training sample, a split of 0 gives no validation set
+        let len = dataset.len();
+        let split = (((1.0 - val_split) * len as f64) as usize).clamp(1, len);
+        let train = dataset.slice(0, split);
+        let validation = if split == len { None } else { Some(dataset.slice(split, len)) };
 
-    // println!("{}", posibility);
+        let hidden = arg("hidden", 128);
+        let model = Sequential::new()
+            .push(Dense::new(dataset.shape().0, hidden).activation(arg("activation", ActivationKind::Relu)).initializer(Initializer::He))
+            .push(Dense::new(hidden, arg("classes", 10)));
+        let optimizer = Sgd::new(arg("lr", 0.1)).momentum(arg("momentum", 0.9)).weight_decay(arg("weight-decay", 0.0));
+        let mut trainer = Trainer::new(model, Loss::new(LossKind::CrossEntropyLogits), optimizer)
+            .epochs(arg("epochs", 10))
+            .batch_size(arg("batch-size", 32))
+            .lr_decay(arg("lr-decay", 1.0))
+            .input_scale(arg("input-scale", 1.0 / 255.0))
+            .verbose(true);
+        trainer.fit(&train, validation.as_ref());
+        return
+    }
 
     // let number1: i32 = 80;
     // let number2: i64 = 80;
     // let sum = number1 + number2 as i32;
     // println!("{:?}", sum);
-    let area = lin_alg::Determinant::from([
-        [-17.142857, 9.964465, 1.0, 1.0, 1.0, 1.0],
-        [-8.571428, 17.0 , 1.0, 1.0, 1.0, 1.0],
-        [0.0, 14.0, 1.0, 1.0, 1.0, 1.0],
-        [0.0, 0.0, 1.0, 1.0, 1.0, 1.0],
-        [-8.571428, -3.0, 1.0, 1.0, 1.0, 1.0],
-        [-17.142857, 4.246938, 1.0, 1.0, 1.0, 1.0]
-    ]);
-    // -610.65
-    println!("{}", area);
-    println!("{}", area.cal())
+    let polygon = [
+        lin_alg::Vector::from([-17.142857, 9.964465]),
+        lin_alg::Vector::from([-8.571428, 17.0]),
+        lin_alg::Vector::from([0.0, 14.0]),
+        lin_alg::Vector::from([0.0, 0.0]),
+        lin_alg::Vector::from([-8.571428, -3.0]),
+        lin_alg::Vector::from([-17.142857, 4.246938])
+    ];
+    // 255.93
+    println!("{}", geometry::area(&polygon))
 }
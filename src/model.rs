@@ -0,0 +1,169 @@
+use crate::layer::Layer;
+use crate::lin_alg::Matrix;
+
+// layers run one after another, the output of each is the input of the next
+pub struct Sequential {
+    layers: Vec<Box<dyn Layer>>,
+    training: bool
+}
+
+impl Default for Sequential {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Sequential {
+    pub fn new() -> Self {
+        Self { layers: Vec::new(), training: true }
+    }
+
+    // checks the new layer takes as many rows as the last one gives
+    pub fn push(mut self, layer: impl Layer + 'static) -> Self {
+        let given = self.layers.iter().rev().find_map(|i| i.output_size());
+        if let (Some(given), Some(taken)) = (given, layer.input_size()) {
+            if given != taken {
+                panic!("Layer {} takes {} rows, but the layer before it gives {}!", self.layers.len(), taken, given)
+            }
+        }
+        let mut layer: Box<dyn Layer> = Box::new(layer);
+        layer.set_training(self.training);
+        self.layers.push(layer);
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.layers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.layers.is_empty()
+    }
+
+    pub fn layers(&self) -> &[Box<dyn Layer>] {
+        &self.layers
+    }
+
+    pub fn train(&mut self) {
+        self.set_training(true)
+    }
+
+    pub fn eval(&mut self) {
+        self.set_training(false)
+    }
+}
+
+impl Layer for Sequential {
+    fn forward(&mut self, input: &Matrix) -> Matrix {
+        let mut out = input.clone();
+        for layer in self.layers.iter_mut() {
+            out = layer.forward(&out)
+        };
+        out
+    }
+
+    fn backward(&mut self, grad: &Matrix) -> Matrix {
+        let mut grad = grad.clone();
+        for layer in self.layers.iter_mut().rev() {
+            grad = layer.backward(&grad)
+        };
+        grad
+    }
+
+    fn predict(&self, input: &Matrix) -> Matrix {
+        let mut out = input.clone();
+        for layer in self.layers.iter() {
+            out = layer.predict(&out)
+        };
+        out
+    }
+
+    fn parameters(&self) -> Vec<&Matrix> {
+        self.layers.iter().flat_map(|i| i.parameters()).collect()
+    }
+
+    fn gradients(&self) -> Vec<&Matrix> {
+        self.layers.iter().flat_map(|i| i.gradients()).collect()
+    }
+
+    fn parameters_mut(&mut self) -> Vec<(&mut Matrix, &Matrix)> {
+        self.layers.iter_mut().flat_map(|i| i.parameters_mut()).collect()
+    }
+
+    fn zero_grad(&mut self) {
+        for layer in self.layers.iter_mut() {
+            layer.zero_grad()
+        }
+    }
+
+    fn input_size(&self) -> Option<usize> {
+        self.layers.iter().find_map(|i| i.input_size())
+    }
+
+    fn output_size(&self) -> Option<usize> {
+        self.layers.iter().rev().find_map(|i| i.output_size())
+    }
+
+    fn set_training(&mut self, training: bool) {
+        self.training = training;
+        for layer in self.layers.iter_mut() {
+            layer.set_training(training)
+        }
+    }
+
+    fn is_training(&self) -> bool {
+        self.training
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::func::ActivationKind;
+    use crate::gradcheck::gradcheck;
+    use crate::gradcheck::testing::sample;
+    use crate::layer::Dense;
+    use crate::loss::{one_hot, Loss, LossKind};
+
+    fn two_layers() -> Sequential {
+        Sequential::new()
+            .push(Dense::from_parts(sample((4, 5), 13, 0.7), sample((1, 5), 14, 0.2), ActivationKind::Tanh))
+            .push(Dense::from_parts(sample((5, 3), 15, 0.7), sample((1, 3), 16, 0.2), ActivationKind::Identity))
+    }
+
+    #[test]
+    fn backward_matches_gradcheck() {
+        let x = sample((6, 4), 12, 1.0);
+        let target = one_hot(&[0, 1, 2, 1, 0, 2], 3);
+        let loss = Loss::new(LossKind::CrossEntropyLogits);
+        let mut model = two_layers();
+        let output = model.forward(&x);
+        let input_grad = model.backward(&loss.backward(&output, &target));
+        let check = gradcheck(|i| loss.forward(&model.predict(i), &target).scalar(), &x, &input_grad);
+        assert!(check.passed(1e-5, 1e-7), "sequential input: {}", check);
+
+        // the gradient of the first weights, through both layers
+        let weights = model.parameters()[0].clone();
+        let analytic = model.gradients()[0].clone();
+        let check = gradcheck(|w| {
+            let mut model = two_layers();
+            *model.parameters_mut()[0].0 = w.clone();
+            loss.forward(&model.predict(&x), &target).scalar()
+        }, &weights, &analytic);
+        assert!(check.passed(1e-5, 1e-7), "sequential weights: {}", check)
+    }
+
+    #[test]
+    fn sizes_and_parameters() {
+        let model = two_layers();
+        assert_eq!((model.len(), model.input_size(), model.output_size()), (2, Some(4), Some(3)));
+        assert_eq!(model.parameters().len(), 4);
+        assert_eq!(model.predict(&sample((7, 4), 1, 1.0)).shape(), (7, 3))
+    }
+
+    #[test]
+    #[should_panic(expected = "Layer 1 takes 4 rows, but the layer before it gives 5!")]
+    fn push_shape_mismatch() {
+        let _ = Sequential::new().push(Dense::new(3, 5)).push(Dense::new(4, 2));
+    }
+}
@@ -0,0 +1,115 @@
+use crate::lin_alg::Matrix;
+
+pub trait Optimizer {
+    // moves every parameter against its gradient, the list must come in the same order every step
+    fn step(&mut self, params: Vec<(&mut Matrix, &Matrix)>);
+    fn learning_rate(&self) -> f64;
+    fn set_learning_rate(&mut self, learning_rate: f64);
+}
+
+// per-parameter state, made on the first step once the shapes are known
+fn state(buf: &mut Vec<Matrix>, params: &[(&mut Matrix, &Matrix)]) {
+    if buf.len() != params.len() {
+        *buf = params.iter().map(|i| Matrix::zeros(i.0.shape())).collect()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Sgd {
+    learning_rate: f64,
+    momentum: f64,
+    // L2 penalty added to the gradient as weight_decay * param
+    weight_decay: f64,
+    velocity: Vec<Matrix>
+}
+
+impl Sgd {
+    pub fn new(learning_rate: f64) -> Self {
+        Self { learning_rate, momentum: 0.0, weight_decay: 0.0, velocity: Vec::new() }
+    }
+
+    pub fn momentum(mut self, momentum: f64) -> Self {
+        self.momentum = momentum;
+        self
+    }
+
+    pub fn weight_decay(mut self, weight_decay: f64) -> Self {
+        self.weight_decay = weight_decay;
+        self
+    }
+}
+
+impl Optimizer for Sgd {
+    fn step(&mut self, mut params: Vec<(&mut Matrix, &Matrix)>) {
+        state(&mut self.velocity, &params);
+        for ((param, grad), velocity) in params.iter_mut().zip(self.velocity.iter_mut()) {
+            let grad = grad.clone() + param.strech(self.weight_decay);
+            // v = momentum * v + g, p = p - lr * v
+            *velocity = velocity.strech(self.momentum) + grad;
+            **param -= velocity.strech(self.learning_rate)
+        }
+    }
+
+    fn learning_rate(&self) -> f64 {
+        self.learning_rate
+    }
+
+    fn set_learning_rate(&mut self, learning_rate: f64) {
+        self.learning_rate = learning_rate
+    }
+}
+
+// Kingma & Ba (2015) with bias-corrected moments
+#[derive(Clone, Debug)]
+pub struct Adam {
+    learning_rate: f64,
+    beta1: f64,
+    beta2: f64,
+    eps: f64,
+    steps: i32,
+    m: Vec<Matrix>,
+    v: Vec<Matrix>
+}
+
+impl Adam {
+    pub fn new(learning_rate: f64) -> Self {
+        Self { learning_rate, beta1: 0.9, beta2: 0.999, eps: 1e-8, steps: 0, m: Vec::new(), v: Vec::new() }
+    }
+
+    pub fn betas(mut self, beta1: f64, beta2: f64) -> Self {
+        (self.beta1, self.beta2) = (beta1, beta2);
+        self
+    }
+
+    pub fn eps(mut self, eps: f64) -> Self {
+        self.eps = eps;
+        self
+    }
+}
+
+impl Optimizer for Adam {
+    fn step(&mut self, mut params: Vec<(&mut Matrix, &Matrix)>) {
+        state(&mut self.m, &params);
+        state(&mut self.v, &params);
+        self.steps += 1;
+        let (beta1, beta2, eps) = (self.beta1, self.beta2, self.eps);
+        let correction1 = 1.0 - beta1.powi(self.steps);
+        let correction2 = 1.0 - beta2.powi(self.steps);
+        let step_size = self.learning_rate / correction1;
+
+        for (index, (param, grad)) in params.iter_mut().enumerate() {
+            self.m[index] = self.m[index].oper_with((*grad).clone(), &|m, g| beta1 * m + (1.0 - beta1) * g);
+            self.v[index] = self.v[index].oper_with((*grad).clone(), &|v, g| beta2 * v + (1.0 - beta2) * g * g);
+            let update = self.m[index].oper_with(self.v[index].clone(), &|m, v| m / ((v / correction2).sqrt() + eps));
+            **param -= update.strech(step_size)
+        }
+    }
+
+    fn learning_rate(&self) -> f64 {
+        self.learning_rate
+    }
+
+    fn set_learning_rate(&mut self, learning_rate: f64) {
+        self.learning_rate = learning_rate
+    }
+}
@@ -0,0 +1,252 @@
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+
+use crate::data_pro::DataSet;
+use crate::layer::Layer;
+use crate::lin_alg::Matrix;
+use crate::loss::{one_hot, Loss, Reduction};
+use crate::optim::Optimizer;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EpochReport {
+    // 1-based
+    pub epoch: usize,
+    pub loss: f64,
+    pub accuracy: f64,
+    // loss and accuracy on the validation set, when there is one
+    pub validation: Option<(f64, f64)>,
+    pub learning_rate: f64
+}
+
+impl std::fmt::Display for EpochReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "epoch {}: loss {:.4}, accuracy {:.2}%", self.epoch, self.loss, self.accuracy * 100.0)?;
+        if let Some((loss, accuracy)) = self.validation {
+            write!(f, ", val loss {:.4}, val accuracy {:.2}%", loss, accuracy * 100.0)?
+        }
+        write!(f, ", lr {}", self.learning_rate)
+    }
+}
+
+// trains a classifier whose output has a row per class
+pub struct Trainer<M: Layer, O: Optimizer> {
+    pub model: M,
+    pub loss: Loss,
+    pub optimizer: O,
+    epochs: usize,
+    batch_size: usize,
+    shuffle: bool,
+    // the learning rate is multiplied by this after every epoch
+    lr_decay: f64,
+    // every input is multiplied by this, e.g. 1 / 255 for raw pixels
+    input_scale: f64,
+    verbose: bool,
+    rng: StdRng
+}
+
+// the index of the largest row in every column
+fn argmax(output: &Matrix) -> Vec<usize> {
+    let (cols, rows) = output.shape();
+    (0..cols).map(|x| {
+        (0..rows).fold(0, |best, y| if output.get(x, y) > output.get(x, best) { y } else { best })
+    }).collect()
+}
+
+impl<M: Layer, O: Optimizer> Trainer<M, O> {
+    pub fn new(model: M, loss: Loss, optimizer: O) -> Self {
+        if loss.reduction == Reduction::None { panic!("The trainer needs a reduced loss!") }
+        Self {
+            model,
+            loss,
+            optimizer,
+            epochs: 10,
+            batch_size: 32,
+            shuffle: true,
+            lr_decay: 1.0,
+            input_scale: 1.0,
+            verbose: false,
+            rng: StdRng::from_entropy()
+        }
+    }
+
+    pub fn epochs(mut self, epochs: usize) -> Self {
+        self.epochs = epochs;
+        self
+    }
+
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        if batch_size == 0 { panic!("The batch size can't be zero!") }
+        self.batch_size = batch_size;
+        self
+    }
+
+    pub fn shuffle(mut self, shuffle: bool) -> Self {
+        self.shuffle = shuffle;
+        self
+    }
+
+    pub fn lr_decay(mut self, lr_decay: f64) -> Self {
+        self.lr_decay = lr_decay;
+        self
+    }
+
+    pub fn input_scale(mut self, input_scale: f64) -> Self {
+        self.input_scale = input_scale;
+        self
+    }
+
+    // prints every epoch report as it finishes
+    pub fn verbose(mut self, verbose: bool) -> Self {
+        self.verbose = verbose;
+        self
+    }
+
+    // makes the shuffling reproducible
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.rng = StdRng::seed_from_u64(seed);
+        self
+    }
+
+    fn classes(&self) -> usize {
+        self.model.output_size().expect("The model must have a known output size!")
+    }
+
+    fn batch(&self, data: &DataSet, indexes: &[usize]) -> (Matrix, Matrix, Vec<usize>) {
+        let (input, label) = data.batch(indexes);
+        let label = label.into_iter().map(|i| {
+            if i < 0 { panic!("Labels can't be negative!") }
+            i as usize
+        }).collect::<Vec<_>>();
+        (input.strech(self.input_scale), one_hot(&label, self.classes()), label)
+    }
+
+    // the mean loss over the samples, with a batch of n weighing n times a single sample
+    fn batch_loss(&self, output: &Matrix, target: &Matrix) -> f64 {
+        let val = self.loss.forward(output, target).scalar();
+        match self.loss.reduction {
+            Reduction::Mean => val * output.shape().0 as f64,
+            _ => val
+        }
+    }
+
+    pub fn fit(&mut self, train: &DataSet, validation: Option<&DataSet>) -> Vec<EpochReport> {
+        if train.is_empty() { panic!("The training set is empty!") }
+        let mut reports = Vec::new();
+        let mut order = (0..train.len()).collect::<Vec<_>>();
+
+        for epoch in 1..=self.epochs {
+            if self.shuffle { order.shuffle(&mut self.rng) }
+            self.model.set_training(true);
+            let (mut total, mut correct) = (0.0, 0);
+            for indexes in order.chunks(self.batch_size) {
+                let (input, target, label) = self.batch(train, indexes);
+                self.model.zero_grad();
+                let output = self.model.forward(&input);
+                total += self.batch_loss(&output, &target);
+                correct += argmax(&output).iter().zip(label.iter()).filter(|(i, j)| i == j).count();
+                let grad = self.loss.backward(&output, &target);
+                self.model.backward(&grad);
+                self.optimizer.step(self.model.parameters_mut())
+            };
+
+            let report = EpochReport {
+                epoch,
+                loss: total / train.len() as f64,
+                accuracy: correct as f64 / train.len() as f64,
+                validation: validation.map(|i| self.evaluate(i)),
+                learning_rate: self.optimizer.learning_rate()
+            };
+            if self.verbose { println!("{}", report) }
+            reports.push(report);
+            self.optimizer.set_learning_rate(self.optimizer.learning_rate() * self.lr_decay)
+        };
+        reports
+    }
+
+    // (loss, accuracy) without touching the gradients, the model is left in the mode it was in
+    pub fn evaluate(&mut self, data: &DataSet) -> (f64, f64) {
+        if data.is_empty() { panic!("The dataset is empty!") }
+        let training = self.model.is_training();
+        self.model.set_training(false);
+        let (mut total, mut correct) = (0.0, 0);
+        let order = (0..data.len()).collect::<Vec<_>>();
+        for indexes in order.chunks(self.batch_size) {
+            let (input, target, label) = self.batch(data, indexes);
+            let output = self.model.predict(&input);
+            total += self.batch_loss(&output, &target);
+            correct += argmax(&output).iter().zip(label.iter()).filter(|(i, j)| i == j).count()
+        };
+        self.model.set_training(training);
+        (total / data.len() as f64, correct as f64 / data.len() as f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::func::ActivationKind;
+    use crate::gradcheck::testing::sample;
+    use crate::layer::Dense;
+    use crate::loss::LossKind;
+    use crate::model::Sequential;
+    use crate::optim::Sgd;
+
+    // two classes split by the line x = y
+    fn toy(len: usize, seed: usize) -> DataSet {
+        let val = sample((2, len), seed, 2.0);
+        let label = (0..len).map(|i| (val.get(0, i) > val.get(1, i)) as i64).collect();
+        DataSet::new(val, label)
+    }
+
+    fn trainer() -> Trainer<Sequential, Sgd> {
+        let model = Sequential::new()
+            .push(Dense::from_parts(sample((2, 8), 1, 0.5), Matrix::zeros((1, 8)), ActivationKind::Tanh))
+            .push(Dense::from_parts(sample((8, 2), 2, 0.5), Matrix::zeros((1, 2)), ActivationKind::Identity));
+        Trainer::new(model, Loss::new(LossKind::CrossEntropyLogits), Sgd::new(0.2).momentum(0.5))
+            .epochs(15)
+            .batch_size(8)
+            .lr_decay(0.9)
+            .seed(7)
+    }
+
+    #[test]
+    fn fit_reports_every_epoch() {
+        let (train, validation) = (toy(60, 3), toy(20, 4));
+        let reports = trainer().fit(&train, Some(&validation));
+        assert_eq!(reports.len(), 15);
+        assert_eq!(reports.iter().map(|i| i.epoch).collect::<Vec<_>>(), (1..=15).collect::<Vec<_>>());
+        assert!(reports[14].loss < reports[0].loss * 0.5, "{} -> {}", reports[0].loss, reports[14].loss);
+        assert!(reports[14].accuracy > 0.9, "{}", reports[14].accuracy);
+        for report in reports.iter() {
+            assert!((0.0..=1.0).contains(&report.accuracy));
+            let (loss, accuracy) = report.validation.expect("Every epoch has a validation report!");
+            assert!(loss.is_finite() && (0.0..=1.0).contains(&accuracy))
+        };
+        // the decay applies after each epoch, so the first one runs on the initial rate
+        for (index, report) in reports.iter().enumerate() {
+            assert!((report.learning_rate - 0.2 * 0.9f64.powi(index as i32)).abs() < 1e-12)
+        }
+    }
+
+    #[test]
+    fn fit_is_reproducible_and_skips_missing_validation() {
+        let train = toy(30, 5);
+        let (first, second) = (trainer().fit(&train, None), trainer().fit(&train, None));
+        assert!(first.iter().all(|i| i.validation.is_none()));
+        assert_eq!(first, second)
+    }
+
+    #[test]
+    fn evaluate_keeps_the_mode() {
+        let data = toy(10, 6);
+        let mut trainer = trainer();
+        trainer.model.eval();
+        let (loss, accuracy) = trainer.evaluate(&data);
+        assert!(!trainer.model.is_training());
+        assert!(loss.is_finite() && (0.0..=1.0).contains(&accuracy));
+        trainer.model.train();
+        assert_eq!(trainer.evaluate(&data), (loss, accuracy));
+        assert!(trainer.model.is_training())
+    }
+}